@@ -310,8 +310,32 @@ if (!nativeBinding) {
   throw new Error(`Failed to load native binding`)
 }
 
-const { diff, diffPaths, formatOutput } = nativeBinding
+const { DiffStream, ErrorCode, DiffType, Severity, Differ, NdjsonWriter, diff, diffPaths, diffBuffers, diffTensors, diffAsync, diffPathsAsync, diffStream, diffPathsStream, formatOutput, renderHtmlReport, renderMarkdownReport, summarize, rankChanges, toJsonPatch, applyPatch, patch, unpatch, merge3, invert, compose } = nativeBinding
+
+module.exports.DiffStream = DiffStream
+module.exports.ErrorCode = ErrorCode
+module.exports.DiffType = DiffType
+module.exports.Severity = Severity
+module.exports.Differ = Differ
+module.exports.NdjsonWriter = NdjsonWriter
 
 module.exports.diff = diff
 module.exports.diffPaths = diffPaths
+module.exports.diffBuffers = diffBuffers
+module.exports.diffTensors = diffTensors
+module.exports.diffAsync = diffAsync
+module.exports.diffPathsAsync = diffPathsAsync
+module.exports.diffStream = diffStream
+module.exports.diffPathsStream = diffPathsStream
 module.exports.formatOutput = formatOutput
+module.exports.renderHtmlReport = renderHtmlReport
+module.exports.renderMarkdownReport = renderMarkdownReport
+module.exports.summarize = summarize
+module.exports.rankChanges = rankChanges
+module.exports.toJsonPatch = toJsonPatch
+module.exports.applyPatch = applyPatch
+module.exports.patch = patch
+module.exports.unpatch = unpatch
+module.exports.merge3 = merge3
+module.exports.invert = invert
+module.exports.compose = compose
@@ -0,0 +1,115 @@
+use std::fmt;
+use std::mem::ManuallyDrop;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use napi::bindgen_prelude::*;
+use napi::{JsFunction, JsObject, Ref};
+
+/// Cooperative cancellation flag shared between the JS thread and a running diff.
+///
+/// The walker polls it at every file and tensor boundary and bails out with
+/// [`Cancelled`] once it has been set.
+#[derive(Clone, Default)]
+pub(crate) struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    /// Build a token that is cancelled when the given `AbortSignal` fires.
+    ///
+    /// The returned listener has to be removed once the comparison is over, so
+    /// a signal reused for many comparisons does not collect listeners.
+    pub(crate) fn from_signal(
+        env: &Env,
+        signal: &JsObject,
+    ) -> Result<(Self, Option<AbortListener>)> {
+        let token = Self::default();
+
+        if signal.get_named_property::<bool>("aborted")? {
+            token.cancel();
+            return Ok((token, None));
+        }
+
+        let flag = token.clone();
+        let on_abort = env.create_function_from_closure("onabort", move |_ctx| {
+            flag.cancel();
+            Ok(())
+        })?;
+        let listener = AbortListener {
+            signal: ManuallyDrop::new(env.create_reference(signal)?),
+            on_abort: ManuallyDrop::new(env.create_reference(&on_abort)?),
+        };
+        let mut listener_options = env.create_object()?;
+        listener_options.set_named_property("once", true)?;
+
+        let add_event_listener: JsFunction = signal.get_named_property("addEventListener")?;
+        add_event_listener.call(
+            Some(signal),
+            &[
+                env.create_string("abort")?.into_unknown(),
+                on_abort.into_unknown(),
+                listener_options.into_unknown(),
+            ],
+        )?;
+
+        Ok((token, Some(listener)))
+    }
+
+    pub(crate) fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Return [`Cancelled`] if cancellation has been requested.
+    pub(crate) fn check(&self) -> anyhow::Result<()> {
+        if self.is_cancelled() {
+            Err(Cancelled.into())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// The `abort` listener `CancelToken::from_signal` added to an `AbortSignal`.
+///
+/// The references are only released by `remove`; a listener dropped without
+/// it stays registered, like one added without keeping it.
+pub(crate) struct AbortListener {
+    signal: ManuallyDrop<Ref<()>>,
+    on_abort: ManuallyDrop<Ref<()>>,
+}
+
+impl AbortListener {
+    /// Remove the listener from the signal.
+    pub(crate) fn remove(mut self, env: Env) -> Result<()> {
+        let signal: JsObject = env.get_reference_value(&self.signal)?;
+        let on_abort: JsFunction = env.get_reference_value(&self.on_abort)?;
+
+        let remove_event_listener: JsFunction = signal.get_named_property("removeEventListener")?;
+        remove_event_listener.call(
+            Some(&signal),
+            &[
+                env.create_string("abort")?.into_unknown(),
+                on_abort.into_unknown(),
+            ],
+        )?;
+
+        self.signal.unref(env)?;
+        self.on_abort.unref(env)?;
+        Ok(())
+    }
+}
+
+/// Error raised when a comparison stops because its token was cancelled.
+#[derive(Debug)]
+pub(crate) struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Diff cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
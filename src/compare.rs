@@ -0,0 +1,67 @@
+use anyhow::Result;
+use diffai_core::{diff as core_diff, DiffOptions};
+use serde_json::Value;
+
+use crate::entry::DiffEntry;
+use crate::filter::{PathFilter, TypeFilter};
+use crate::tensors::{self, Model};
+use crate::tolerance::Tolerance;
+
+/// Options of one comparison: those `diffai_core` applies while diffing and
+/// those applied to its results here.
+#[derive(Default)]
+pub(crate) struct CompareOptions {
+    pub(crate) core: DiffOptions,
+    pub(crate) tolerance: Tolerance,
+    pub(crate) filter: PathFilter,
+    pub(crate) types: TypeFilter,
+    /// Whether to compute every `ExtendedStats` of the tensors of model files
+    pub(crate) extended_stats: bool,
+}
+
+impl CompareOptions {
+    /// Drop the results these options filter out or tolerate.
+    ///
+    /// Paths are matched as they are returned, so results of a directory
+    /// comparison are refined after their file name has been prefixed. A
+    /// tensor that gained NaN or infinite values is never tolerated.
+    pub(crate) fn refine(&self, results: &mut Vec<DiffEntry>) {
+        results.retain(|entry| {
+            self.types.accepts(entry.diff_type())
+                && self.filter.accepts(entry.path())
+                && (entry.introduced.is_some() || !self.tolerance.absorbs(&entry.result))
+        });
+    }
+}
+
+/// `diffai_core::diff` followed by `CompareOptions::refine`.
+pub(crate) fn diff(
+    old: &Value,
+    new: &Value,
+    options: Option<&CompareOptions>,
+) -> Result<Vec<DiffEntry>> {
+    let mut results = core_diff(old, new, options.map(|o| &o.core))?
+        .into_iter()
+        .map(DiffEntry::from)
+        .collect();
+    if let Some(options) = options {
+        options.refine(&mut results);
+    }
+    Ok(results)
+}
+
+/// `diffai_core::diff` of two parsed model files, followed by the results of
+/// the tensors that gained NaN or infinite values. The results are not
+/// refined.
+pub(crate) fn diff_models(
+    old: &Model,
+    new: &Model,
+    options: Option<&CompareOptions>,
+) -> Result<Vec<DiffEntry>> {
+    let mut results: Vec<DiffEntry> = core_diff(&old.value, &new.value, options.map(|o| &o.core))?
+        .into_iter()
+        .map(DiffEntry::from)
+        .collect();
+    results.extend(tensors::introduced(old, new));
+    Ok(results)
+}
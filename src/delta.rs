@@ -0,0 +1,528 @@
+use std::collections::HashMap;
+
+use diffai_core::DiffResult;
+use serde_json::Value;
+
+use crate::entry::DiffEntry;
+use crate::error::{DiffaiError, DiffaiResult, ErrorCode};
+use crate::keypath::{self, Segment};
+use crate::patch::{self, Direction};
+use crate::paths::result_path_mut;
+use crate::{diff_type_name, result_path};
+
+/// The result describing the opposite change, from new back to old.
+///
+/// `WeightSignificantChange` only has a magnitude, which is the same both ways.
+/// A tensor that gained NaN or infinite values loses them the other way, so
+/// the inverse of `TensorNanIntroduced` is a `TensorStatsChanged` unless the
+/// counts tell otherwise.
+pub(crate) fn invert(entry: DiffEntry) -> DiffEntry {
+    match (entry.result, entry.extended) {
+        (DiffResult::TensorStatsChanged(path, old, new), Some(extended)) => {
+            let (old_extended, new_extended) = *extended;
+            DiffEntry::tensor_stats(path, new, old, (new_extended, old_extended), None)
+        }
+        (result, _) => invert_result(result).into(),
+    }
+}
+
+fn invert_result(result: DiffResult) -> DiffResult {
+    match result {
+        DiffResult::Added(path, value) => DiffResult::Removed(path, value),
+        DiffResult::Removed(path, value) => DiffResult::Added(path, value),
+        DiffResult::Modified(path, old, new) => DiffResult::Modified(path, new, old),
+        DiffResult::TypeChanged(path, old, new) => DiffResult::TypeChanged(path, new, old),
+        DiffResult::TensorShapeChanged(path, old, new) => {
+            DiffResult::TensorShapeChanged(path, new, old)
+        }
+        DiffResult::TensorStatsChanged(path, old, new) => {
+            DiffResult::TensorStatsChanged(path, new, old)
+        }
+        DiffResult::TensorDataChanged(path, old, new) => {
+            DiffResult::TensorDataChanged(path, new, old)
+        }
+        DiffResult::ModelArchitectureChanged(path, old, new) => {
+            DiffResult::ModelArchitectureChanged(path, new, old)
+        }
+        DiffResult::WeightSignificantChange(path, magnitude) => {
+            DiffResult::WeightSignificantChange(path, magnitude)
+        }
+        DiffResult::ActivationFunctionChanged(path, old, new) => {
+            DiffResult::ActivationFunctionChanged(path, new, old)
+        }
+        DiffResult::LearningRateChanged(path, old, new) => {
+            DiffResult::LearningRateChanged(path, new, old)
+        }
+        DiffResult::OptimizerChanged(path, old, new) => {
+            DiffResult::OptimizerChanged(path, new, old)
+        }
+        DiffResult::LossChange(path, old, new) => DiffResult::LossChange(path, new, old),
+        DiffResult::AccuracyChange(path, old, new) => DiffResult::AccuracyChange(path, new, old),
+        DiffResult::ModelVersionChanged(path, old, new) => {
+            DiffResult::ModelVersionChanged(path, new, old)
+        }
+    }
+}
+
+/// Combine the results from A to B and from B to C into the results from A
+/// to C.
+///
+/// Results for the same path and type are merged into one, and dropped when
+/// C ends up equal to A. A change below a value that the other delta added,
+/// replaced or removed is folded into that value. `WeightSignificantChange`
+/// magnitudes are added, which bounds the change from A to C. Array indices
+/// are rebased past the elements either delta removed or inserted.
+pub(crate) fn compose(ab: Vec<DiffEntry>, bc: Vec<DiffEntry>) -> DiffaiResult<Vec<DiffEntry>> {
+    let ab_shifts = Shifts::of(&ab);
+    let bc_shifts = Shifts::of(&bc);
+
+    // Both deltas are compared at the indices of B, except for the elements
+    // only A or only C has
+    let (ab_frames, mut ab): (Vec<Frame>, Vec<Option<DiffEntry>>) = ab
+        .into_iter()
+        .map(|mut entry| {
+            let frame = match &entry.result {
+                DiffResult::Removed(path, _) if element(path).is_some() => Frame::A,
+                DiffResult::Added(..) => Frame::B,
+                _ => {
+                    rebase(&mut entry.result, |parent, index| {
+                        ab_shifts.forward(parent, index)
+                    });
+                    Frame::B
+                }
+            };
+            (frame, Some(entry))
+        })
+        .unzip();
+    let (bc_frames, mut bc): (Vec<Frame>, Vec<Option<DiffEntry>>) = bc
+        .into_iter()
+        .map(|mut entry| {
+            let frame = match &entry.result {
+                DiffResult::Added(path, _) if element(path).is_some() => Frame::C,
+                DiffResult::Added(..) => {
+                    rebase(&mut entry.result, |parent, index| {
+                        bc_shifts.backward(parent, index)
+                    });
+                    Frame::B
+                }
+                _ => Frame::B,
+            };
+            (frame, Some(entry))
+        })
+        .unzip();
+
+    fold_descendants(&mut ab, &mut bc, Direction::Forward)?;
+    fold_descendants(&mut bc, &mut ab, Direction::Reverse)?;
+
+    let mut positions = HashMap::new();
+    for (position, (result, &frame)) in ab.iter().zip(&ab_frames).enumerate() {
+        if let Some(result) = result {
+            positions.insert(key(&result.result, frame), position);
+        }
+    }
+
+    let mut added_by_bc = Vec::new();
+    for (second, frame) in bc.into_iter().zip(bc_frames) {
+        let Some(second) = second else { continue };
+        match positions.get(&key(&second.result, frame)) {
+            Some(&position) => {
+                let first = ab[position].take().expect("each key is combined once");
+                ab[position] = combine(first, second)?;
+            }
+            None => added_by_bc.push((frame, second)),
+        }
+    }
+
+    // Back to the indices `patch` expects: those of C for inserted values and
+    // those of A for everything else
+    let results = ab
+        .into_iter()
+        .zip(ab_frames)
+        .filter_map(|(entry, frame)| Some((frame, entry?)))
+        .chain(added_by_bc)
+        .map(|(frame, mut entry)| {
+            match (frame, &entry.result) {
+                (Frame::A | Frame::C, _) => {}
+                (Frame::B, DiffResult::Added(..)) => rebase(&mut entry.result, |parent, index| {
+                    bc_shifts.forward(parent, index)
+                }),
+                (Frame::B, _) => rebase(&mut entry.result, |parent, index| {
+                    ab_shifts.backward(parent, index)
+                }),
+            }
+            entry
+        })
+        .collect();
+    Ok(merge_replacements(results))
+}
+
+/// Which value the array indices of a result path refer to.
+///
+/// Removing or inserting an array element shifts the elements after it, so
+/// one element can have a different index in each of A, B and C.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Frame {
+    A,
+    B,
+    C,
+}
+
+/// Indices of the array elements a delta removes, in the old array, and
+/// inserts, in the new array, keyed by the path of the array.
+#[derive(Default)]
+struct Shifts(HashMap<String, (Vec<usize>, Vec<usize>)>);
+
+impl Shifts {
+    fn of(entries: &[DiffEntry]) -> Self {
+        let mut shifts = Self::default();
+        for entry in entries {
+            let (path, inserted) = match &entry.result {
+                DiffResult::Removed(path, _) => (path, false),
+                DiffResult::Added(path, _) => (path, true),
+                _ => continue,
+            };
+            if let Some((parent, index)) = element(path) {
+                let (removed, added) = shifts.0.entry(parent).or_default();
+                match inserted {
+                    true => added.push(index),
+                    false => removed.push(index),
+                }
+            }
+        }
+        for (removed, added) in shifts.0.values_mut() {
+            removed.sort_unstable();
+            added.sort_unstable();
+        }
+        shifts
+    }
+
+    /// Index in the new array of the element at `index` in the old one, if
+    /// it was not removed.
+    fn forward(&self, parent: &str, index: usize) -> Option<usize> {
+        let Some((removed, added)) = self.0.get(parent) else {
+            return Some(index);
+        };
+        if removed.binary_search(&index).is_ok() {
+            return None;
+        }
+        let mut index = index - removed.partition_point(|&removed| removed < index);
+        for &added in added {
+            if added <= index {
+                index += 1;
+            }
+        }
+        Some(index)
+    }
+
+    /// Index in the old array of the element at `index` in the new one, if
+    /// it was not inserted.
+    fn backward(&self, parent: &str, index: usize) -> Option<usize> {
+        let Some((removed, added)) = self.0.get(parent) else {
+            return Some(index);
+        };
+        if added.binary_search(&index).is_ok() {
+            return None;
+        }
+        let mut index = index - added.partition_point(|&added| added < index);
+        for &removed in removed {
+            if removed <= index {
+                index += 1;
+            }
+        }
+        Some(index)
+    }
+}
+
+/// Path of the array and index of a path ending in an array index.
+fn element(path: &str) -> Option<(String, usize)> {
+    let mut segments = keypath::split(path);
+    match segments.pop()? {
+        Segment::Index(index) => Some((keypath::join(&segments), index)),
+        _ => None,
+    }
+}
+
+/// Map the array indices in the path of `result`, outermost first. Indices of
+/// elements `map` knows nothing about are kept.
+fn rebase(result: &mut DiffResult, map: impl Fn(&str, usize) -> Option<usize>) {
+    let path = result_path_mut(result);
+    let mut segments = keypath::split(path);
+    for n in 0..segments.len() {
+        if let Segment::Index(index) = segments[n] {
+            if let Some(index) = map(&keypath::join(&segments[..n]), index) {
+                segments[n] = Segment::Index(index);
+            }
+        }
+    }
+    *path = keypath::join(&segments);
+}
+
+/// Merge an array element removed at an index of A and one inserted at the
+/// same index of C into a single change, when nothing else shifts the
+/// element between them.
+fn merge_replacements(results: Vec<DiffEntry>) -> Vec<DiffEntry> {
+    let shifts = Shifts::of(&results);
+    let mut removals = HashMap::new();
+    for (position, entry) in results.iter().enumerate() {
+        if let DiffResult::Removed(path, _) = &entry.result {
+            if let Some(element) = element(path) {
+                removals.insert(element, position);
+            }
+        }
+    }
+
+    let mut merged = Vec::new();
+    for (position, entry) in results.iter().enumerate() {
+        let DiffResult::Added(path, _) = &entry.result else {
+            continue;
+        };
+        let Some((parent, index)) = element(path) else {
+            continue;
+        };
+        let Some(&removal) = removals.get(&(parent.clone(), index)) else {
+            continue;
+        };
+        // Where the other removals and insertions move the element at `index`
+        let (removed, added) = &shifts.0[&parent];
+        let others = Shifts(HashMap::from([(
+            parent.clone(),
+            (
+                removed.iter().copied().filter(|&i| i != index).collect(),
+                added.iter().copied().filter(|&i| i != index).collect(),
+            ),
+        )]));
+        if others.forward(&parent, index) == Some(index) {
+            merged.push((removal, position));
+        }
+    }
+
+    let mut slots: Vec<Option<DiffEntry>> = results.into_iter().map(Some).collect();
+    for (removal, insertion) in merged {
+        let removed = slots[removal].take().map(|entry| entry.result);
+        let added = slots[insertion].take().map(|entry| entry.result);
+        if let (Some(DiffResult::Removed(path, old)), Some(DiffResult::Added(_, new))) =
+            (removed, added)
+        {
+            slots[removal] = value_change(path, old, new).map(DiffEntry::from);
+        }
+    }
+    slots.into_iter().flatten().collect()
+}
+
+/// Fold changes of `inner` below a value held by a change of `outer` into that
+/// value: `Forward` applies later changes to the value `outer` produced,
+/// `Reverse` reverts earlier changes on the value `outer` started from.
+fn fold_descendants(
+    outer: &mut [Option<DiffEntry>],
+    inner: &mut [Option<DiffEntry>],
+    direction: Direction,
+) -> DiffaiResult<()> {
+    let mut holders = HashMap::new();
+    for (position, entry) in outer.iter().enumerate() {
+        let Some(DiffEntry { result, .. }) = entry else {
+            continue;
+        };
+        let holds_value = match direction {
+            Direction::Forward => matches!(
+                result,
+                DiffResult::Added(..) | DiffResult::Modified(..) | DiffResult::TypeChanged(..)
+            ),
+            Direction::Reverse => matches!(
+                result,
+                DiffResult::Removed(..) | DiffResult::Modified(..) | DiffResult::TypeChanged(..)
+            ),
+        };
+        if holds_value {
+            holders.insert(keypath::split(result_path(result)), position);
+        }
+    }
+    if holders.is_empty() {
+        return Ok(());
+    }
+
+    for slot in inner.iter_mut() {
+        let Some(result) = slot
+            .as_ref()
+            .map(|entry| &entry.result)
+            .filter(|result| is_edit(result))
+        else {
+            continue;
+        };
+        let segments = keypath::split(result_path(result));
+        let Some((depth, &position)) =
+            (0..segments.len()).find_map(|depth| Some((depth, holders.get(&segments[..depth])?)))
+        else {
+            continue;
+        };
+
+        let relative = with_path(
+            slot.take().expect("checked above").result,
+            keypath::join(&segments[depth..]),
+        );
+        let holder = &mut outer[position]
+            .as_mut()
+            .expect("holders are not taken")
+            .result;
+        let value = match direction {
+            Direction::Forward => new_value(holder),
+            Direction::Reverse => old_value(holder),
+        }
+        .expect("holders hold a value");
+
+        patch::apply_results(value, [&relative], direction).map_err(|e| {
+            DiffaiError::new(
+                ErrorCode::InvalidArgument,
+                format!("The results do not follow each other: {e}"),
+            )
+        })?;
+    }
+    Ok(())
+}
+
+/// Results with the same key describe the same aspect of the same location.
+fn key(result: &DiffResult, frame: Frame) -> (Frame, String, &'static str) {
+    let kind = if is_edit(result) {
+        "value"
+    } else {
+        diff_type_name(result)
+    };
+    (frame, result_path(result).to_string(), kind)
+}
+
+/// Merge the change from A to B and the change from B to C at one location,
+/// keeping the extended statistics of A and C. The merged stats change is a
+/// `TensorNanIntroduced` or `TensorInfIntroduced` if the counts of A and C
+/// say so, or else if either change was.
+fn combine(first: DiffEntry, second: DiffEntry) -> DiffaiResult<Option<DiffEntry>> {
+    use DiffResult::*;
+
+    if let (TensorStatsChanged(path, old, _), TensorStatsChanged(_, _, new)) =
+        (&first.result, &second.result)
+    {
+        if let (Some(first_extended), Some(second_extended)) = (&first.extended, &second.extended) {
+            let extended = (first_extended.0.clone(), second_extended.1.clone());
+            return Ok((old != new || extended.0 != extended.1).then(|| {
+                DiffEntry::tensor_stats(
+                    path.clone(),
+                    old.clone(),
+                    new.clone(),
+                    extended,
+                    first.introduced.or(second.introduced),
+                )
+            }));
+        }
+    }
+    let introduced = first.introduced.or(second.introduced);
+
+    let combined = match (first.result, second.result) {
+        (Added(path, _), Modified(_, _, new) | TypeChanged(_, _, new)) => Some(Added(path, new)),
+        (Added(..), Removed(..)) => None,
+        (Removed(path, old), Added(_, new)) => value_change(path, old, new),
+        (
+            Modified(path, old, _) | TypeChanged(path, old, _),
+            Modified(_, _, new) | TypeChanged(_, _, new),
+        ) => value_change(path, old, new),
+        (Modified(path, old, _) | TypeChanged(path, old, _), Removed(..)) => {
+            Some(Removed(path, old))
+        }
+        (TensorShapeChanged(path, old, _), TensorShapeChanged(_, _, new)) => {
+            (old != new).then_some(TensorShapeChanged(path, old, new))
+        }
+        (TensorStatsChanged(path, old, _), TensorStatsChanged(_, _, new)) => {
+            return Ok((old != new).then_some(DiffEntry {
+                result: TensorStatsChanged(path, old, new),
+                introduced,
+                extended: None,
+            }));
+        }
+        (TensorDataChanged(path, old, _), TensorDataChanged(_, _, new)) => {
+            (old != new).then_some(TensorDataChanged(path, old, new))
+        }
+        (ModelArchitectureChanged(path, old, _), ModelArchitectureChanged(_, _, new)) => {
+            (old != new).then_some(ModelArchitectureChanged(path, old, new))
+        }
+        (WeightSignificantChange(path, first), WeightSignificantChange(_, second)) => {
+            Some(WeightSignificantChange(path, first + second))
+        }
+        (ActivationFunctionChanged(path, old, _), ActivationFunctionChanged(_, _, new)) => {
+            (old != new).then_some(ActivationFunctionChanged(path, old, new))
+        }
+        (LearningRateChanged(path, old, _), LearningRateChanged(_, _, new)) => {
+            (old != new).then_some(LearningRateChanged(path, old, new))
+        }
+        (OptimizerChanged(path, old, _), OptimizerChanged(_, _, new)) => {
+            (old != new).then_some(OptimizerChanged(path, old, new))
+        }
+        (LossChange(path, old, _), LossChange(_, _, new)) => {
+            (old != new).then_some(LossChange(path, old, new))
+        }
+        (AccuracyChange(path, old, _), AccuracyChange(_, _, new)) => {
+            (old != new).then_some(AccuracyChange(path, old, new))
+        }
+        (ModelVersionChanged(path, old, _), ModelVersionChanged(_, _, new)) => {
+            (old != new).then_some(ModelVersionChanged(path, old, new))
+        }
+        (first, second) => {
+            return Err(DiffaiError::new(
+                ErrorCode::InvalidArgument,
+                format!(
+                    "Cannot compose the {} and {} results at '{}': the results do not follow each other",
+                    diff_type_name(&first),
+                    diff_type_name(&second),
+                    result_path(&first)
+                ),
+            ))
+        }
+    };
+    Ok(combined.map(DiffEntry::from))
+}
+
+/// `Modified`, or `TypeChanged` when the JSON type differs, unless nothing changed.
+fn value_change(path: String, old: Value, new: Value) -> Option<DiffResult> {
+    if old == new {
+        None
+    } else if std::mem::discriminant(&old) == std::mem::discriminant(&new) {
+        Some(DiffResult::Modified(path, old, new))
+    } else {
+        Some(DiffResult::TypeChanged(path, old, new))
+    }
+}
+
+fn is_edit(result: &DiffResult) -> bool {
+    matches!(
+        result,
+        DiffResult::Added(..)
+            | DiffResult::Removed(..)
+            | DiffResult::Modified(..)
+            | DiffResult::TypeChanged(..)
+    )
+}
+
+fn new_value(result: &mut DiffResult) -> Option<&mut Value> {
+    match result {
+        DiffResult::Added(_, value)
+        | DiffResult::Modified(_, _, value)
+        | DiffResult::TypeChanged(_, _, value) => Some(value),
+        _ => None,
+    }
+}
+
+fn old_value(result: &mut DiffResult) -> Option<&mut Value> {
+    match result {
+        DiffResult::Removed(_, value)
+        | DiffResult::Modified(_, value, _)
+        | DiffResult::TypeChanged(_, value, _) => Some(value),
+        _ => None,
+    }
+}
+
+/// The same edit at another path; other result types are returned unchanged.
+fn with_path(result: DiffResult, path: String) -> DiffResult {
+    match result {
+        DiffResult::Added(_, value) => DiffResult::Added(path, value),
+        DiffResult::Removed(_, value) => DiffResult::Removed(path, value),
+        DiffResult::Modified(_, old, new) => DiffResult::Modified(path, old, new),
+        DiffResult::TypeChanged(_, old, new) => DiffResult::TypeChanged(path, old, new),
+        other => other,
+    }
+}
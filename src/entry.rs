@@ -0,0 +1,165 @@
+use diffai_core::{DiffResult, TensorStats};
+use serde::ser::{SerializeTupleVariant, Serializer};
+use serde::Serialize;
+
+use crate::tensors::ExtendedStats;
+use crate::{diff_type_name, result_path};
+
+/// Non-finite values a tensor gained, reported instead of `TensorStatsChanged`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Introduced {
+    Nan,
+    Inf,
+}
+
+impl Introduced {
+    pub(crate) fn parse(diff_type: &str) -> Option<Self> {
+        match diff_type {
+            "TensorNanIntroduced" => Some(Self::Nan),
+            "TensorInfIntroduced" => Some(Self::Inf),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn diff_type(self) -> &'static str {
+        match self {
+            Self::Nan => "TensorNanIntroduced",
+            Self::Inf => "TensorInfIntroduced",
+        }
+    }
+
+    /// What the new tensor gained over the old one according to their NaN and
+    /// infinity counts, NaN values first, or `fallback` if a count is missing.
+    pub(crate) fn between(
+        old: &ExtendedStats,
+        new: &ExtendedStats,
+        fallback: Option<Self>,
+    ) -> Option<Self> {
+        match (old.nan_count, old.inf_count, new.nan_count, new.inf_count) {
+            (Some(old_nan), Some(old_inf), Some(new_nan), Some(new_inf)) => {
+                if new_nan > old_nan {
+                    Some(Self::Nan)
+                } else if new_inf > old_inf {
+                    Some(Self::Inf)
+                } else {
+                    None
+                }
+            }
+            _ => fallback,
+        }
+    }
+}
+
+/// One result as this crate reports it: a `DiffResult` plus what
+/// `diffai_core` has no room for.
+///
+/// Only `TensorStatsChanged` results have more: they are reported as
+/// `TensorNanIntroduced` or `TensorInfIntroduced` when the tensor gained
+/// non-finite values, and can carry the `ExtendedStats` of both tensors.
+#[derive(Debug)]
+pub struct DiffEntry {
+    pub(crate) result: DiffResult,
+    pub(crate) introduced: Option<Introduced>,
+    /// Extended statistics of the old and new tensor
+    pub(crate) extended: Option<Box<(ExtendedStats, ExtendedStats)>>,
+}
+
+impl DiffEntry {
+    /// A `TensorStatsChanged` result with the extended statistics of both
+    /// tensors, labelled by the non-finite values the new one gained.
+    pub(crate) fn tensor_stats(
+        path: String,
+        old: TensorStats,
+        new: TensorStats,
+        extended: (ExtendedStats, ExtendedStats),
+        fallback: Option<Introduced>,
+    ) -> Self {
+        Self {
+            result: DiffResult::TensorStatsChanged(path, old, new),
+            introduced: Introduced::between(&extended.0, &extended.1, fallback),
+            extended: Some(Box::new(extended)),
+        }
+    }
+
+    pub(crate) fn path(&self) -> &str {
+        result_path(&self.result)
+    }
+
+    /// `diffType` of the entry, as in `JsDiffResult`
+    pub(crate) fn diff_type(&self) -> &'static str {
+        match self.introduced {
+            Some(introduced) => introduced.diff_type(),
+            None => diff_type_name(&self.result),
+        }
+    }
+
+    /// What the tensor of a `TensorNanIntroduced` or `TensorInfIntroduced`
+    /// entry gained, e.g. "gained NaN values: 0 -> 12" when the counts are
+    /// known.
+    pub(crate) fn gain(&self) -> Option<String> {
+        let (values, count): (_, fn(&ExtendedStats) -> Option<usize>) = match self.introduced? {
+            Introduced::Nan => ("NaN", |stats| stats.nan_count),
+            Introduced::Inf => ("infinite", |stats| stats.inf_count),
+        };
+        let counts = match self.extended() {
+            (Some(old), Some(new)) => count(old).zip(count(new)),
+            _ => None,
+        };
+        Some(match counts {
+            Some((old, new)) => format!("gained {values} values: {old} -> {new}"),
+            None => format!("gained {values} values"),
+        })
+    }
+
+    /// Extended statistics of the old and new tensor, if any
+    pub(crate) fn extended(&self) -> (Option<&ExtendedStats>, Option<&ExtendedStats>) {
+        match self.extended.as_deref() {
+            Some((old, new)) => (Some(old), Some(new)),
+            None => (None, None),
+        }
+    }
+}
+
+impl From<DiffResult> for DiffEntry {
+    fn from(result: DiffResult) -> Self {
+        Self {
+            result,
+            introduced: None,
+            extended: None,
+        }
+    }
+}
+
+/// Serialized like the `DiffResult` it holds, so that the JSON and YAML output
+/// formats match `diffai_core`, but with the `diffType` of the entry and the
+/// extended statistics next to the others.
+impl Serialize for DiffEntry {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let DiffResult::TensorStatsChanged(path, old, new) = &self.result else {
+            return self.result.serialize(serializer);
+        };
+        let (old_extended, new_extended) = self.extended();
+
+        // Index of `TensorStatsChanged` in `DiffResult`
+        let mut fields =
+            serializer.serialize_tuple_variant("DiffResult", 5, self.diff_type(), 3)?;
+        fields.serialize_field(path)?;
+        fields.serialize_field(&Stats {
+            stats: old,
+            extended: old_extended,
+        })?;
+        fields.serialize_field(&Stats {
+            stats: new,
+            extended: new_extended,
+        })?;
+        fields.end()
+    }
+}
+
+#[derive(Serialize)]
+struct Stats<'s> {
+    #[serde(flatten)]
+    stats: &'s TensorStats,
+    #[serde(flatten)]
+    extended: Option<&'s ExtendedStats>,
+}
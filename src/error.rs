@@ -0,0 +1,172 @@
+use std::fmt;
+use std::io;
+use std::path::Path;
+
+use napi::bindgen_prelude::*;
+use napi::{Env, JsObject};
+use napi_derive::napi;
+
+use crate::cancel::Cancelled;
+
+/// Machine-readable `code` of every error thrown by diffai-js
+#[napi(string_enum)]
+#[derive(Debug)]
+pub enum ErrorCode {
+    /// A file or directory does not exist
+    #[napi(value = "DIFFAI_FILE_NOT_FOUND")]
+    FileNotFound,
+    /// A file or directory exists but could not be read
+    #[napi(value = "DIFFAI_IO_ERROR")]
+    IoError,
+    /// The model format or output format is not supported
+    #[napi(value = "DIFFAI_UNSUPPORTED_FORMAT")]
+    UnsupportedFormat,
+    /// A model file is corrupt or could not be parsed
+    #[napi(value = "DIFFAI_PARSE_ERROR")]
+    ParseError,
+    /// An option has an invalid value
+    #[napi(value = "DIFFAI_INVALID_OPTION")]
+    InvalidOption,
+    /// An argument, such as a result object or a tensor, is invalid
+    #[napi(value = "DIFFAI_INVALID_ARGUMENT")]
+    InvalidArgument,
+    /// The comparison was stopped through an `AbortSignal`
+    #[napi(value = "DIFFAI_CANCELLED")]
+    Cancelled,
+    /// Comparing or formatting the values failed
+    #[napi(value = "DIFFAI_DIFF_ERROR")]
+    DiffError,
+}
+
+pub(crate) type DiffaiResult<T> = std::result::Result<T, DiffaiError>;
+
+/// An error with a code, the file it concerns and the underlying cause.
+///
+/// It is thrown to JS as an `Error` with `code`, and `path` and `cause`
+/// properties when they are known.
+#[derive(Debug)]
+pub struct DiffaiError {
+    code: ErrorCode,
+    message: String,
+    path: Option<String>,
+    cause: Option<String>,
+}
+
+impl DiffaiError {
+    pub(crate) fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            path: None,
+            cause: None,
+        }
+    }
+
+    /// An error reading `path`, classified by the I/O error kind.
+    pub(crate) fn io(error: &io::Error, path: &Path) -> Self {
+        Self::io_error("read", error, path)
+    }
+
+    /// An error writing `path`, classified like `io`.
+    pub(crate) fn write(error: &io::Error, path: &Path) -> Self {
+        Self::io_error("write", error, path)
+    }
+
+    fn io_error(action: &str, error: &io::Error, path: &Path) -> Self {
+        let code = match error.kind() {
+            io::ErrorKind::NotFound => ErrorCode::FileNotFound,
+            _ => ErrorCode::IoError,
+        };
+
+        Self::new(
+            code,
+            format!("Cannot {action} '{}': {error}", path.display()),
+        )
+        .with_path(path)
+        .with_cause(error)
+    }
+
+    /// An error parsing the model file at `path`.
+    ///
+    /// I/O failures keep their own code; anything else is a parse error.
+    pub(crate) fn parse(error: anyhow::Error, path: &Path) -> Self {
+        if let Some(io_error) = error.downcast_ref::<io::Error>() {
+            return Self::io(io_error, path);
+        }
+
+        Self::new(
+            ErrorCode::ParseError,
+            format!("Failed to parse '{}': {error}", path.display()),
+        )
+        .with_path(path)
+        .with_cause(format!("{error:#}"))
+    }
+
+    pub(crate) fn with_path(mut self, path: impl AsRef<Path>) -> Self {
+        self.path = Some(path.as_ref().to_string_lossy().to_string());
+        self
+    }
+
+    pub(crate) fn with_cause(mut self, cause: impl fmt::Display) -> Self {
+        self.cause = Some(cause.to_string());
+        self
+    }
+
+    /// Build the JS `Error` object that is thrown or used to reject a Promise.
+    pub(crate) fn into_napi(self, env: &Env) -> Error {
+        match self.to_js_error(env) {
+            Ok(error) => Error::from(error.into_unknown()),
+            Err(e) => e,
+        }
+    }
+
+    fn to_js_error(&self, env: &Env) -> Result<JsObject> {
+        let mut error = env.create_error(Error::from_reason(self.message.clone()))?;
+        error.set_named_property("code", self.code)?;
+
+        if let Some(path) = &self.path {
+            error.set_named_property("path", path.as_str())?;
+        }
+        if let Some(cause) = &self.cause {
+            let cause = env.create_error(Error::from_reason(cause.clone()))?;
+            error.set_named_property("cause", cause)?;
+        }
+
+        Ok(error)
+    }
+}
+
+impl fmt::Display for DiffaiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for DiffaiError {}
+
+impl From<anyhow::Error> for DiffaiError {
+    fn from(error: anyhow::Error) -> Self {
+        let error = match error.downcast::<DiffaiError>() {
+            Ok(error) => return error,
+            Err(error) => error,
+        };
+
+        if error.is::<Cancelled>() {
+            return Self::new(ErrorCode::Cancelled, error.to_string());
+        }
+
+        Self::new(ErrorCode::DiffError, format!("Diff error: {error}")).with_cause(error)
+    }
+}
+
+/// Turn a `DiffaiResult` (or any result whose error converts into one) into a
+/// napi result carrying the JS error object.
+pub(crate) trait IntoJsResult<T> {
+    fn into_js(self, env: &Env) -> Result<T>;
+}
+
+impl<T, E: Into<DiffaiError>> IntoJsResult<T> for std::result::Result<T, E> {
+    fn into_js(self, env: &Env) -> Result<T> {
+        self.map_err(|e| e.into().into_napi(env))
+    }
+}
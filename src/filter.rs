@@ -0,0 +1,101 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::error::DiffaiResult;
+use crate::pattern::{JsPathPattern, PathPattern};
+use crate::severity::{self, Severities, Severity};
+
+/// The `include` and `exclude` globs, matched against result paths.
+///
+/// A glob also matches everything below the paths it matches. Globs starting
+/// with `!` are negated: in `include` they exclude, and in `exclude` they keep
+/// paths that would otherwise be excluded.
+#[derive(Default)]
+pub(crate) struct PathFilter {
+    include: Vec<PathPattern>,
+    exclude: Vec<PathPattern>,
+    exceptions: Vec<PathPattern>,
+}
+
+impl PathFilter {
+    pub(crate) fn new(include: Vec<String>, exclude: Vec<String>) -> DiffaiResult<Self> {
+        let mut filter = Self::default();
+        for glob in include {
+            match glob.strip_prefix('!') {
+                Some(glob) => filter.exclude.push(compile(glob)?),
+                None => filter.include.push(compile(&glob)?),
+            }
+        }
+        for glob in exclude {
+            match glob.strip_prefix('!') {
+                Some(glob) => filter.exceptions.push(compile(glob)?),
+                None => filter.exclude.push(compile(&glob)?),
+            }
+        }
+        Ok(filter)
+    }
+
+    /// Whether results at `path` are kept.
+    pub(crate) fn accepts(&self, path: &str) -> bool {
+        let included =
+            self.include.is_empty() || self.include.iter().any(|p| p.matches_within(path));
+        let excluded = self.exclude.iter().any(|p| p.matches_within(path))
+            && !self.exceptions.iter().any(|p| p.matches_within(path));
+        included && !excluded
+    }
+
+    /// Whether every result at or below `path` is dropped, so the value there
+    /// does not need to be read at all.
+    ///
+    /// Only exclusions are considered, since an `include` glob or an exception
+    /// may still match something below `path`.
+    pub(crate) fn excludes_all_below(&self, path: &str) -> bool {
+        self.exceptions.is_empty() && self.exclude.iter().any(|p| p.matches_within(path))
+    }
+}
+
+/// The `onlyTypes`, `excludeTypes` and `minSeverity` options.
+#[derive(Default)]
+pub(crate) struct TypeFilter {
+    only: Option<HashSet<&'static str>>,
+    excluded: HashSet<&'static str>,
+    severities: Severities,
+    min_severity: Option<Severity>,
+}
+
+impl TypeFilter {
+    pub(crate) fn new(
+        only: Option<Vec<String>>,
+        exclude: Vec<String>,
+        severities: HashMap<String, String>,
+        min_severity: Option<String>,
+    ) -> DiffaiResult<Self> {
+        let parse_types = |names: Vec<String>| {
+            names
+                .iter()
+                .map(|name| severity::parse_diff_type(name))
+                .collect::<DiffaiResult<HashSet<_>>>()
+        };
+
+        Ok(Self {
+            only: only.map(parse_types).transpose()?,
+            excluded: parse_types(exclude)?,
+            severities: Severities::new(severities)?,
+            min_severity: min_severity.as_deref().map(severity::parse).transpose()?,
+        })
+    }
+
+    /// Whether results of `diff_type` are kept.
+    pub(crate) fn accepts(&self, diff_type: &str) -> bool {
+        self.only
+            .as_ref()
+            .is_none_or(|only| only.contains(diff_type))
+            && !self.excluded.contains(diff_type)
+            && self
+                .min_severity
+                .is_none_or(|min| self.severities.of(diff_type) >= min)
+    }
+}
+
+fn compile(glob: &str) -> DiffaiResult<PathPattern> {
+    PathPattern::compile(JsPathPattern::Glob(glob.to_string()))
+}
@@ -0,0 +1,216 @@
+use std::io::Cursor;
+
+use anyhow::{anyhow, Result};
+use diffai_core::FileFormat;
+use serde_json::Value;
+
+use crate::error::{DiffaiError, DiffaiResult, ErrorCode};
+use crate::safetensors::parse_safetensors;
+use crate::tensors::Model;
+
+/// Parse a user supplied format name such as `"safetensors"` or `"pt"`.
+pub(crate) fn parse_format_name(name: &str) -> Result<FileFormat> {
+    match name.to_lowercase().as_str() {
+        "safetensors" => Ok(FileFormat::Safetensors),
+        "pytorch" | "pt" | "pth" => Ok(FileFormat::PyTorch),
+        "numpy" | "npy" | "npz" => Ok(FileFormat::NumPy),
+        "matlab" | "mat" => Ok(FileFormat::Matlab),
+        _ => Err(anyhow!(
+            "Unsupported file format: '{name}'. Expected one of: safetensors, pytorch, numpy, matlab"
+        )),
+    }
+}
+
+/// Guess the format of an in-memory model file from its leading bytes.
+pub(crate) fn sniff_format(bytes: &[u8]) -> Option<FileFormat> {
+    if bytes.starts_with(b"\x93NUMPY") {
+        return Some(FileFormat::NumPy);
+    }
+    if bytes.starts_with(b"MATLAB") {
+        return Some(FileFormat::Matlab);
+    }
+    if bytes.starts_with(b"PK\x03\x04") {
+        // Both torch.save archives and .npz files are zip archives
+        if contains(bytes, b"data.pkl") {
+            return Some(FileFormat::PyTorch);
+        }
+        if contains(bytes, b".npy") {
+            return Some(FileFormat::NumPy);
+        }
+        return None;
+    }
+    // Legacy torch.save output is a bare pickle stream
+    if bytes.len() > 2 && bytes[0] == 0x80 && bytes[1] <= 5 {
+        return Some(FileFormat::PyTorch);
+    }
+    if bytes.len() > 8 {
+        let header_len = u64::from_le_bytes(bytes[..8].try_into().unwrap());
+        if header_len <= (bytes.len() - 8) as u64 && bytes[8] == b'{' {
+            return Some(FileFormat::Safetensors);
+        }
+    }
+    None
+}
+
+/// Reject the formats whose buffers `parse_bytes` cannot read.
+///
+/// The core keeps nothing but the model type of NumPy and MATLAB files, so any
+/// two buffers of these formats would compare equal.
+pub(crate) fn check_readable(format: FileFormat) -> DiffaiResult<()> {
+    match format {
+        FileFormat::Safetensors | FileFormat::PyTorch => Ok(()),
+        FileFormat::NumPy | FileFormat::Matlab => Err(DiffaiError::new(
+            ErrorCode::UnsupportedFormat,
+            format!(
+                "Comparing {} buffers is not supported yet; their arrays are not read",
+                format_name(format)
+            ),
+        )),
+    }
+}
+
+/// Parse an in-memory model file into the same JSON layout the core parsers
+/// produce for files on disk, minus the `file_path` entries. `extended` is
+/// passed on to `parse_safetensors`.
+pub(crate) fn parse_bytes(bytes: &[u8], format: FileFormat, extended: bool) -> Result<Model> {
+    match format {
+        FileFormat::Safetensors => {
+            parse_safetensors(Cursor::new(bytes), extended, &mut |_, _| Ok(true))
+        }
+        FileFormat::PyTorch => Ok(parse_pytorch_bytes(bytes).into()),
+        FileFormat::NumPy | FileFormat::Matlab => Err(anyhow!(
+            "Reading {} buffers is not supported",
+            format_name(format)
+        )),
+    }
+}
+
+fn format_name(format: FileFormat) -> &'static str {
+    match format {
+        FileFormat::Safetensors => "safetensors",
+        FileFormat::PyTorch => "PyTorch",
+        FileFormat::NumPy => "NumPy",
+        FileFormat::Matlab => "MATLAB",
+    }
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack
+        .windows(needle.len())
+        .any(|window| window == needle)
+}
+
+// Mirrors `diffai_core::parse_pytorch_model`, which only accepts a path.
+fn parse_pytorch_bytes(buffer: &[u8]) -> Value {
+    let mut result = serde_json::Map::new();
+    result.insert(
+        "model_type".to_string(),
+        Value::String("pytorch".to_string()),
+    );
+    result.insert("file_size".to_string(), Value::Number(buffer.len().into()));
+    result.insert("format".to_string(), Value::String("pickle".to_string()));
+
+    let searchable_content = String::from_utf8_lossy(buffer);
+
+    let weight_count = searchable_content.matches("weight").count();
+    let bias_count = searchable_content.matches("bias").count();
+    let conv_count = searchable_content.matches("conv").count();
+    let linear_count =
+        searchable_content.matches("linear").count() + searchable_content.matches("fc.").count();
+    let bn_count =
+        searchable_content.matches("bn").count() + searchable_content.matches("batch_norm").count();
+
+    let mut detected_layers = Vec::new();
+    if conv_count > 0 {
+        detected_layers.push(format!("convolution: {conv_count}"));
+    }
+    if linear_count > 0 {
+        detected_layers.push(format!("linear: {linear_count}"));
+    }
+    if bn_count > 0 {
+        detected_layers.push(format!("batch_norm: {bn_count}"));
+    }
+    if weight_count > 0 {
+        detected_layers.push(format!("weight_params: {weight_count}"));
+    }
+    if bias_count > 0 {
+        detected_layers.push(format!("bias_params: {bias_count}"));
+    }
+    if !detected_layers.is_empty() {
+        result.insert(
+            "detected_components".to_string(),
+            Value::String(detected_layers.join(", ")),
+        );
+    }
+
+    let layer_count = weight_count.max(bias_count / 2);
+    if layer_count > 0 {
+        result.insert(
+            "estimated_layers".to_string(),
+            Value::Number(layer_count.into()),
+        );
+    }
+
+    let architectures = [
+        ("resnet", "ResNet"),
+        ("vgg", "VGG"),
+        ("densenet", "DenseNet"),
+        ("mobilenet", "MobileNet"),
+        ("efficientnet", "EfficientNet"),
+        ("transformer", "Transformer"),
+        ("bert", "BERT"),
+        ("gpt", "GPT"),
+    ];
+    let lowercase_content = searchable_content.to_lowercase();
+    if let Some((_, arch_name)) = architectures
+        .iter()
+        .find(|(pattern, _)| lowercase_content.contains(pattern))
+    {
+        result.insert(
+            "detected_architecture".to_string(),
+            Value::String(arch_name.to_string()),
+        );
+    }
+
+    if searchable_content.contains("optimizer") {
+        result.insert("has_optimizer_state".to_string(), Value::Bool(true));
+    }
+    if searchable_content.contains("epoch") {
+        result.insert("has_training_metadata".to_string(), Value::Bool(true));
+    }
+    if searchable_content.contains("lr") || searchable_content.contains("learning_rate") {
+        result.insert("has_learning_rate".to_string(), Value::Bool(true));
+    }
+
+    result.insert(
+        "binary_size".to_string(),
+        Value::Number(buffer.len().into()),
+    );
+
+    if buffer.len() > 2 && buffer[1] <= 5 {
+        result.insert(
+            "pickle_protocol".to_string(),
+            Value::Number(buffer[1].into()),
+        );
+    }
+
+    result.insert(
+        "structure_fingerprint".to_string(),
+        Value::String(format!("{:x}", structure_hash(&searchable_content))),
+    );
+
+    Value::Object(result)
+}
+
+fn structure_hash(content: &str) -> u64 {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    let mut hasher = DefaultHasher::new();
+    let structure_parts: Vec<&str> = content
+        .matches(|c: char| c.is_alphanumeric() || c == '.')
+        .take(1000)
+        .collect();
+    structure_parts.hash(&mut hasher);
+    hasher.finish()
+}
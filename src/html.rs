@@ -0,0 +1,549 @@
+use std::collections::HashMap;
+use std::fmt::{self, Write};
+
+use diffai_core::{DiffResult, TensorStats};
+
+use crate::entry::DiffEntry;
+use crate::keypath;
+use crate::tensors::{ExtendedStats, Histogram};
+
+/// One of the `ExtendedStats`, as a number
+type Stat = fn(&ExtendedStats) -> Option<f64>;
+
+/// Rows of the statistics table for the `ExtendedStats` of a result.
+const EXTENDED_ROWS: [(&str, Stat); 8] = [
+    ("L1 norm", |stats| stats.l1_norm),
+    ("L2 norm", |stats| stats.l2_norm),
+    ("p1", |stats| stats.p1),
+    ("p50", |stats| stats.p50),
+    ("p99", |stats| stats.p99),
+    ("zeros", |stats| stats.zero_fraction),
+    ("NaN", |stats| stats.nan_count.map(|count| count as f64)),
+    ("infinite", |stats| {
+        stats.inf_count.map(|count| count as f64)
+    }),
+];
+
+/// Size of the charts of a `TensorStatsChanged` result, with room for the row
+/// labels on the left and the axis labels below.
+const CHART_WIDTH: f64 = 320.0;
+const CHART_LABEL_WIDTH: f64 = 32.0;
+const CHART_ROW_HEIGHT: f64 = 16.0;
+const CHART_HEIGHT: f64 = 2.0 * CHART_ROW_HEIGHT + 12.0;
+const HISTOGRAM_ROW_HEIGHT: f64 = 40.0;
+const HISTOGRAM_HEIGHT: f64 = 2.0 * HISTOGRAM_ROW_HEIGHT + 12.0;
+
+const STYLE: &str = r#"
+body { font: 14px/1.5 system-ui, sans-serif; margin: 2rem; color: #1f2328; }
+h1 { font-size: 1.5rem; margin: 0 0 .5rem; }
+code { font: 13px ui-monospace, monospace; }
+.legend { display: flex; flex-wrap: wrap; gap: .5rem; padding: 0; list-style: none; }
+details { margin-left: 1rem; }
+summary { cursor: pointer; }
+summary .count { color: #59636e; font-size: 12px; }
+.result { margin: .25rem 0 .25rem 1rem; padding: .25rem .5rem; border-left: 4px solid; }
+.badge { display: inline-block; padding: 0 .4rem; border-radius: 1rem; font-size: 12px; color: #fff; }
+.change { margin: .25rem 0; }
+.old { color: #cf222e; }
+.new { color: #1a7f37; }
+table { border-collapse: collapse; margin: .25rem 0; }
+th, td { padding: .1rem .6rem; border: 1px solid #d1d9e0; text-align: right; }
+th:first-child { text-align: left; }
+.range .old, .histogram .old { stroke: #cf222e; fill: #cf222e; fill-opacity: .35; }
+.range .new, .histogram .new { stroke: #1a7f37; fill: #1a7f37; fill-opacity: .35; }
+.range text, .histogram text { font-size: 10px; fill: #59636e; }
+.caption { color: #59636e; font-size: 12px; }
+.added { border-color: #1a7f37; } .added .badge, .badge.added { background: #1a7f37; }
+.removed { border-color: #cf222e; } .removed .badge, .badge.removed { background: #cf222e; }
+.modified { border-color: #9a6700; } .modified .badge, .badge.modified { background: #9a6700; }
+.tensor { border-color: #0969da; } .tensor .badge, .badge.tensor { background: #0969da; }
+.model { border-color: #8250df; } .model .badge, .badge.model { background: #8250df; }
+"#;
+
+/// Options of `render`
+pub(crate) struct HtmlOptions {
+    pub(crate) title: String,
+    /// Levels of the path tree that start expanded
+    pub(crate) expand_depth: usize,
+}
+
+impl Default for HtmlOptions {
+    fn default() -> Self {
+        Self {
+            title: "diffai report".to_string(),
+            expand_depth: 1,
+        }
+    }
+}
+
+/// Render a standalone HTML page with the results arranged as a path tree.
+pub(crate) fn render(results: &[DiffEntry], options: &HtmlOptions) -> String {
+    let mut out = String::new();
+    write_page(&mut out, results, options).expect("writing to a String cannot fail");
+    out
+}
+
+fn write_page(out: &mut String, results: &[DiffEntry], options: &HtmlOptions) -> fmt::Result {
+    let title = escape(&options.title);
+    write!(
+        out,
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>{title}</title>\n<style>{STYLE}</style>\n</head>\n<body>\n<h1>{title}</h1>\n"
+    )?;
+    write_legend(out, results)?;
+
+    let mut root = Node::default();
+    for result in results {
+        root.insert(result);
+    }
+    for child in &root.children {
+        child.write(out, 0, options.expand_depth)?;
+    }
+    for result in &root.results {
+        write_result(out, result)?;
+    }
+
+    out.write_str("</body>\n</html>\n")
+}
+
+fn write_legend(out: &mut String, results: &[DiffEntry]) -> fmt::Result {
+    let mut counts: Vec<(&DiffEntry, usize)> = Vec::new();
+    for result in results {
+        let name = result.diff_type();
+        match counts.iter_mut().find(|(r, _)| r.diff_type() == name) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((result, 1)),
+        }
+    }
+
+    let noun = if results.len() == 1 {
+        "difference"
+    } else {
+        "differences"
+    };
+    writeln!(out, "<p>{} {noun}</p>", results.len())?;
+    out.write_str("<ul class=\"legend\">\n")?;
+    for (result, count) in counts {
+        writeln!(
+            out,
+            "<li><span class=\"badge {}\">{}</span> {count}</li>",
+            type_class(&result.result),
+            result.diff_type()
+        )?;
+    }
+    out.write_str("</ul>\n")
+}
+
+/// A segment of the path tree with the results at and below it.
+#[derive(Default)]
+struct Node<'r> {
+    name: String,
+    children: Vec<Node<'r>>,
+    index: HashMap<String, usize>,
+    results: Vec<&'r DiffEntry>,
+    count: usize,
+}
+
+impl<'r> Node<'r> {
+    fn insert(&mut self, result: &'r DiffEntry) {
+        let mut node = self;
+        for segment in keypath::split(result.path()) {
+            node.count += 1;
+            let name = segment.to_string();
+            let position = match node.index.get(&name) {
+                Some(&position) => position,
+                None => {
+                    node.index.insert(name.clone(), node.children.len());
+                    node.children.push(Node {
+                        name,
+                        ..Node::default()
+                    });
+                    node.children.len() - 1
+                }
+            };
+            node = &mut node.children[position];
+        }
+        node.count += 1;
+        node.results.push(result);
+    }
+
+    fn write(&self, out: &mut String, depth: usize, expand_depth: usize) -> fmt::Result {
+        let open = if depth < expand_depth { " open" } else { "" };
+        writeln!(
+            out,
+            "<details{open}><summary><code>{}</code> <span class=\"count\">{}</span></summary>",
+            escape(&self.name),
+            self.count
+        )?;
+        for result in &self.results {
+            write_result(out, result)?;
+        }
+        for child in &self.children {
+            child.write(out, depth + 1, expand_depth)?;
+        }
+        out.write_str("</details>\n")
+    }
+}
+
+fn write_result(out: &mut String, entry: &DiffEntry) -> fmt::Result {
+    writeln!(
+        out,
+        "<div class=\"result {}\"><span class=\"badge\">{}</span> <code>{}</code>",
+        type_class(&entry.result),
+        entry.diff_type(),
+        escape(entry.path())
+    )?;
+    if let Some(gain) = entry.gain() {
+        writeln!(out, "<div class=\"change\">{}</div>", escape(&gain))?;
+    }
+
+    match &entry.result {
+        DiffResult::Added(_, value) => write_values(out, None, Some(&value.to_string()))?,
+        DiffResult::Removed(_, value) => write_values(out, Some(&value.to_string()), None)?,
+        DiffResult::Modified(_, old, new) | DiffResult::TypeChanged(_, old, new) => {
+            write_values(out, Some(&old.to_string()), Some(&new.to_string()))?
+        }
+        DiffResult::TensorShapeChanged(_, old, new) => {
+            write_values(out, Some(&format!("{old:?}")), Some(&format!("{new:?}")))?
+        }
+        DiffResult::TensorStatsChanged(_, old, new) => {
+            write_stats_table(out, old, new, entry.extended())?;
+            write_chart(out, old, new, entry.extended())?;
+        }
+        DiffResult::TensorDataChanged(_, old, new)
+        | DiffResult::LearningRateChanged(_, old, new)
+        | DiffResult::LossChange(_, old, new)
+        | DiffResult::AccuracyChange(_, old, new) => {
+            write_values(out, Some(&number(*old)), Some(&number(*new)))?
+        }
+        DiffResult::WeightSignificantChange(_, magnitude) => writeln!(
+            out,
+            "<div class=\"change\">change magnitude {}</div>",
+            number(*magnitude)
+        )?,
+        DiffResult::ModelArchitectureChanged(_, old, new)
+        | DiffResult::ActivationFunctionChanged(_, old, new)
+        | DiffResult::OptimizerChanged(_, old, new)
+        | DiffResult::ModelVersionChanged(_, old, new) => write_values(out, Some(old), Some(new))?,
+    }
+
+    out.write_str("</div>\n")
+}
+
+fn write_values(out: &mut String, old: Option<&str>, new: Option<&str>) -> fmt::Result {
+    out.write_str("<div class=\"change\">")?;
+    if let Some(old) = old {
+        write!(out, "<code class=\"old\">{}</code>", escape(old))?;
+    }
+    if old.is_some() && new.is_some() {
+        out.write_str(" &rarr; ")?;
+    }
+    if let Some(new) = new {
+        write!(out, "<code class=\"new\">{}</code>", escape(new))?;
+    }
+    out.write_str("</div>\n")
+}
+
+fn write_stats_table(
+    out: &mut String,
+    old: &TensorStats,
+    new: &TensorStats,
+    (old_extended, new_extended): (Option<&ExtendedStats>, Option<&ExtendedStats>),
+) -> fmt::Result {
+    out.write_str("<table>\n<tr><th>stat</th><th>old</th><th>new</th><th>&Delta;</th></tr>\n")?;
+    for (name, old, new) in [
+        ("mean", old.mean, new.mean),
+        ("std", old.std, new.std),
+        ("min", old.min, new.min),
+        ("max", old.max, new.max),
+    ] {
+        write_stat_row(out, name, old, new)?;
+    }
+    // Extended statistics known on both sides
+    let extended = |stat: Stat| Some((stat(old_extended?)?, stat(new_extended?)?));
+    for (name, stat) in EXTENDED_ROWS {
+        if let Some((old, new)) = extended(stat) {
+            write_stat_row(out, name, old, new)?;
+        }
+    }
+    writeln!(
+        out,
+        "<tr><th>shape</th><td>{:?}</td><td>{:?}</td><td></td></tr>",
+        old.shape, new.shape
+    )?;
+    writeln!(
+        out,
+        "<tr><th>dtype</th><td>{}</td><td>{}</td><td></td></tr>",
+        escape(&old.dtype),
+        escape(&new.dtype)
+    )?;
+    writeln!(
+        out,
+        "<tr><th>elements</th><td>{}</td><td>{}</td><td>{}</td></tr>",
+        old.element_count,
+        new.element_count,
+        new.element_count as i128 - old.element_count as i128
+    )?;
+    out.write_str("</table>\n")
+}
+
+fn write_stat_row(out: &mut String, name: &str, old: f64, new: f64) -> fmt::Result {
+    writeln!(
+        out,
+        "<tr><th>{name}</th><td>{}</td><td>{}</td><td>{}</td></tr>",
+        number(old),
+        number(new),
+        number(new - old)
+    )
+}
+
+/// Draw the histograms of both tensors if they have them, or else where their
+/// values lie from their statistics.
+fn write_chart(
+    out: &mut String,
+    old: &TensorStats,
+    new: &TensorStats,
+    extended: (Option<&ExtendedStats>, Option<&ExtendedStats>),
+) -> fmt::Result {
+    let histograms = (
+        extended.0.and_then(|extended| extended.histogram.as_ref()),
+        extended.1.and_then(|extended| extended.histogram.as_ref()),
+    );
+    match histograms {
+        (Some(old), Some(new)) if write_histogram(out, old, new)? => Ok(()),
+        _ => write_range_chart(out, old, new, extended),
+    }
+}
+
+/// Draw the histograms of the old and new values over a shared axis, each
+/// scaled to its largest bin. Nothing is drawn, and `false` returned, if the
+/// histograms are empty or span no range.
+fn write_histogram(out: &mut String, old: &Histogram, new: &Histogram) -> Result<bool, fmt::Error> {
+    let low = old.low.min(new.low);
+    let high = old.high.max(new.high);
+    let histograms = [old, new];
+    if !(low.is_finite() && high.is_finite() && high > low)
+        || histograms
+            .iter()
+            .any(|histogram| histogram.counts.is_empty() || histogram.high < histogram.low)
+    {
+        return Ok(false);
+    }
+
+    let x = |value: f64| {
+        CHART_LABEL_WIDTH + (value - low) / (high - low) * (CHART_WIDTH - CHART_LABEL_WIDTH)
+    };
+    let caption = "finite values per bin, scaled to the largest bin";
+    writeln!(
+        out,
+        "<svg class=\"histogram\" width=\"{CHART_WIDTH}\" height=\"{HISTOGRAM_HEIGHT}\" \
+         viewBox=\"0 0 {CHART_WIDTH} {HISTOGRAM_HEIGHT}\" role=\"img\">"
+    )?;
+    writeln!(out, "<title>{caption}</title>")?;
+    for (row, (class, histogram)) in ["old", "new"].into_iter().zip(histograms).enumerate() {
+        let bottom = (row + 1) as f64 * HISTOGRAM_ROW_HEIGHT;
+        writeln!(
+            out,
+            "<text x=\"0\" y=\"{:.2}\">{class}</text>",
+            bottom - HISTOGRAM_ROW_HEIGHT / 2.0 + 4.0
+        )?;
+        let largest = histogram.counts.iter().copied().max().unwrap_or(0).max(1);
+        let width = (histogram.high - histogram.low) / histogram.counts.len() as f64;
+        for (bin, &count) in histogram.counts.iter().enumerate() {
+            if count == 0 {
+                continue;
+            }
+            let start = x(histogram.low + bin as f64 * width);
+            let end = x(histogram.low + (bin + 1) as f64 * width);
+            let height = count as f64 / largest as f64 * (HISTOGRAM_ROW_HEIGHT - 2.0);
+            writeln!(
+                out,
+                "<rect class=\"{class}\" x=\"{start:.2}\" y=\"{:.2}\" width=\"{:.2}\" \
+                 height=\"{height:.2}\"><title>{count}</title></rect>",
+                bottom - height,
+                (end - start).max(1.0)
+            )?;
+        }
+    }
+    writeln!(
+        out,
+        "<text x=\"{CHART_LABEL_WIDTH}\" y=\"{HISTOGRAM_HEIGHT}\">{}</text>\
+         <text x=\"{CHART_WIDTH}\" y=\"{HISTOGRAM_HEIGHT}\" text-anchor=\"end\">{}</text>",
+        number(low),
+        number(high)
+    )?;
+    out.write_str("</svg>\n")?;
+    writeln!(out, "<div class=\"caption\">{caption}</div>")?;
+    Ok(true)
+}
+
+/// Where the values of a tensor lie: `whisker` spans all of them, `bar` the
+/// bulk of them, and `tick` marks their center.
+struct Spread {
+    whisker: (f64, f64),
+    bar: (f64, f64),
+    tick: f64,
+}
+
+impl Spread {
+    /// From the 1st, 50th and 99th percentiles, if known.
+    fn percentiles(stats: &TensorStats, extended: Option<&ExtendedStats>) -> Option<Self> {
+        let extended = extended?;
+        Some(Self {
+            whisker: (stats.min, stats.max),
+            bar: (extended.p1?, extended.p99?),
+            tick: extended.p50?,
+        })
+    }
+
+    /// From the mean and std, with the bar clipped to the min and max.
+    fn moments(stats: &TensorStats) -> Self {
+        Self {
+            whisker: (stats.min, stats.max),
+            bar: (
+                (stats.mean - stats.std).max(stats.min),
+                (stats.mean + stats.std).min(stats.max),
+            ),
+            tick: stats.mean,
+        }
+    }
+
+    fn is_finite(&self) -> bool {
+        [
+            self.whisker.0,
+            self.whisker.1,
+            self.bar.0,
+            self.bar.1,
+            self.tick,
+        ]
+        .iter()
+        .all(|value| value.is_finite())
+    }
+}
+
+/// Draw where the old and new values lie over a shared axis.
+///
+/// Without histograms only statistics are known, not the values, so the chart
+/// shows nothing else: the percentiles when both tensors have them, or else
+/// the mean and std. It is left out when a statistic is not finite.
+fn write_range_chart(
+    out: &mut String,
+    old: &TensorStats,
+    new: &TensorStats,
+    (old_extended, new_extended): (Option<&ExtendedStats>, Option<&ExtendedStats>),
+) -> fmt::Result {
+    let (spreads, caption) = match (
+        Spread::percentiles(old, old_extended),
+        Spread::percentiles(new, new_extended),
+    ) {
+        (Some(old), Some(new)) => (
+            [old, new],
+            "bar: 1st to 99th percentile, tick: median, line: min to max",
+        ),
+        _ => (
+            [Spread::moments(old), Spread::moments(new)],
+            "bar: mean \u{b1} std, tick: mean, line: min to max",
+        ),
+    };
+    let low = old.min.min(new.min);
+    let high = old.max.max(new.max);
+    if !spreads.iter().all(Spread::is_finite) || high <= low {
+        return Ok(());
+    }
+
+    let x = |value: f64| {
+        CHART_LABEL_WIDTH + (value - low) / (high - low) * (CHART_WIDTH - CHART_LABEL_WIDTH)
+    };
+    writeln!(
+        out,
+        "<svg class=\"range\" width=\"{CHART_WIDTH}\" height=\"{CHART_HEIGHT}\" \
+         viewBox=\"0 0 {CHART_WIDTH} {CHART_HEIGHT}\" role=\"img\">"
+    )?;
+    writeln!(out, "<title>{}</title>", escape(caption))?;
+    for (row, (class, spread)) in ["old", "new"].into_iter().zip(&spreads).enumerate() {
+        let top = row as f64 * CHART_ROW_HEIGHT;
+        let middle = top + CHART_ROW_HEIGHT / 2.0;
+        writeln!(
+            out,
+            "<text x=\"0\" y=\"{:.2}\">{class}</text>",
+            middle + 4.0
+        )?;
+        writeln!(
+            out,
+            "<line class=\"{class}\" x1=\"{:.2}\" y1=\"{middle:.2}\" x2=\"{:.2}\" y2=\"{middle:.2}\"/>",
+            x(spread.whisker.0),
+            x(spread.whisker.1)
+        )?;
+        writeln!(
+            out,
+            "<rect class=\"{class}\" x=\"{:.2}\" y=\"{:.2}\" width=\"{:.2}\" height=\"{:.2}\"/>",
+            x(spread.bar.0),
+            top + 3.0,
+            x(spread.bar.1) - x(spread.bar.0),
+            CHART_ROW_HEIGHT - 6.0
+        )?;
+        writeln!(
+            out,
+            "<line class=\"{class}\" x1=\"{0:.2}\" y1=\"{1:.2}\" x2=\"{0:.2}\" y2=\"{2:.2}\"/>",
+            x(spread.tick),
+            top + 1.0,
+            top + CHART_ROW_HEIGHT - 1.0
+        )?;
+    }
+    writeln!(
+        out,
+        "<text x=\"{CHART_LABEL_WIDTH}\" y=\"{CHART_HEIGHT}\">{}</text>\
+         <text x=\"{CHART_WIDTH}\" y=\"{CHART_HEIGHT}\" text-anchor=\"end\">{}</text>",
+        number(low),
+        number(high)
+    )?;
+    out.write_str("</svg>\n")?;
+    writeln!(out, "<div class=\"caption\">{}</div>", escape(caption))
+}
+
+/// Format a statistic compactly, switching to exponent notation for very
+/// small and very large values.
+pub(crate) fn number(value: f64) -> String {
+    let magnitude = value.abs();
+    if value == 0.0 || (1e-4..1e6).contains(&magnitude) {
+        let fixed = format!("{value:.6}");
+        fixed
+            .trim_end_matches('0')
+            .trim_end_matches('.')
+            .to_string()
+    } else if value.is_finite() {
+        format!("{value:.4e}")
+    } else {
+        value.to_string()
+    }
+}
+
+/// Escape text for HTML element content and attribute values.
+pub(crate) fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// CSS class giving each kind of result its color
+fn type_class(result: &DiffResult) -> &'static str {
+    match result {
+        DiffResult::Added(..) => "added",
+        DiffResult::Removed(..) => "removed",
+        DiffResult::Modified(..) | DiffResult::TypeChanged(..) => "modified",
+        DiffResult::TensorShapeChanged(..)
+        | DiffResult::TensorStatsChanged(..)
+        | DiffResult::TensorDataChanged(..)
+        | DiffResult::WeightSignificantChange(..) => "tensor",
+        _ => "model",
+    }
+}
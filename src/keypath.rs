@@ -0,0 +1,65 @@
+use std::fmt;
+
+/// One step of a result path such as `layers[0].weight` or `items[id=1].v`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum Segment {
+    /// An object key
+    Key(String),
+    /// An array index, `[0]`
+    Index(usize),
+    /// An array element matched through `arrayIdKey`, `[id=1]`
+    Id(String, String),
+}
+
+/// Split a result path into its segments. The empty path is the root value.
+pub(crate) fn split(path: &str) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    let mut rest = path;
+
+    while !rest.is_empty() {
+        if let Some(bracketed) = rest.strip_prefix('[') {
+            let end = bracketed.find(']').unwrap_or(bracketed.len());
+            segments.push(bracket_segment(&bracketed[..end]));
+            rest = bracketed.get(end + 1..).unwrap_or("");
+        } else {
+            let end = rest.find(['.', '[']).unwrap_or(rest.len());
+            segments.push(Segment::Key(rest[..end].to_string()));
+            rest = &rest[end..];
+        }
+        rest = rest.strip_prefix('.').unwrap_or(rest);
+    }
+
+    segments
+}
+
+/// Write segments back as a result path, the inverse of `split`.
+pub(crate) fn join(segments: &[Segment]) -> String {
+    let mut path = String::new();
+    for segment in segments {
+        if !path.is_empty() && matches!(segment, Segment::Key(_)) {
+            path.push('.');
+        }
+        path.push_str(&segment.to_string());
+    }
+    path
+}
+
+fn bracket_segment(inner: &str) -> Segment {
+    if let Some((key, value)) = inner.split_once('=') {
+        return Segment::Id(key.to_string(), value.to_string());
+    }
+    match inner.parse() {
+        Ok(index) => Segment::Index(index),
+        Err(_) => Segment::Key(inner.to_string()),
+    }
+}
+
+impl fmt::Display for Segment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Segment::Key(key) => f.write_str(key),
+            Segment::Index(index) => write!(f, "[{index}]"),
+            Segment::Id(key, value) => write!(f, "[{key}={value}]"),
+        }
+    }
+}
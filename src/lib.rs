@@ -1,16 +1,76 @@
-use diffai_core::{
-    diff as core_diff, diff_paths as core_diff_paths, format_output as core_format_output,
-    DiffOptions, DiffResult, OutputFormat, TensorStats,
-};
+use std::collections::HashMap;
+
+use diffai_core::{DiffOptions, DiffResult, TensorStats};
 use napi::bindgen_prelude::*;
+use napi::{JsObject, JsTypedArray, NapiRaw};
 use napi_derive::napi;
 use regex::Regex;
+use serde::Serialize;
 
-#[napi(object)]
+mod cancel;
+mod compare;
+mod delta;
+mod entry;
+mod error;
+mod filter;
+mod formats;
+mod html;
+mod keypath;
+mod markdown;
+mod merge;
+mod output;
+mod patch;
+mod paths;
+mod pattern;
+mod progress;
+mod rank;
+mod safetensors;
+mod severity;
+mod stream;
+mod summary;
+mod tensors;
+mod tolerance;
+
+use cancel::{AbortListener, CancelToken};
+use compare::CompareOptions;
+use entry::{DiffEntry, Introduced};
+use error::{DiffaiError, DiffaiResult, ErrorCode, IntoJsResult};
+use filter::{PathFilter, TypeFilter};
+use merge::JsMergeResult;
+use patch::JsPatchOperation;
+use paths::Walker;
+use progress::{ProgressCallback, ProgressCallbackRef, ProgressReporter};
+use rank::{JsRankedChange, Metric};
+use summary::JsGroupSummary;
+use tensors::{ExtendedStats, Histogram, TensorView};
+use tolerance::{JsEpsilonRule, Margin, Tolerance};
+
+#[napi(object, object_to_js = false)]
 pub struct JsDiffOptions {
-    /// Numerical comparison tolerance
+    /// Numerical comparison tolerance; the absolute tolerance (`atol`) of the
+    /// "numpy" mode
     pub epsilon: Option<f64>,
 
+    /// How numbers and tensor statistics are compared: "absolute" (the default,
+    /// `|a - b| <= epsilon`), "relative" (`|a - b| <= rtol * max(|a|, |b|)`),
+    /// "numpy" (`|a - b| <= epsilon + rtol * |b|`, with numpy's defaults of
+    /// 1e-8 and 1e-5) or "ulp" (at most `maxUlps` float64 steps apart), where
+    /// `a` is the old and `b` the new number
+    #[napi(ts_type = "'absolute' | 'relative' | 'numpy' | 'ulp'")]
+    pub tolerance_mode: Option<String>,
+
+    /// Relative tolerance of the "relative" and "numpy" modes
+    pub rtol: Option<f64>,
+
+    /// Maximum distance in units in the last place of the "ulp" mode
+    pub max_ulps: Option<f64>,
+
+    /// Tolerances for the numbers at some paths, e.g. `{ pattern: '**.bias', epsilon: 1e-3 }`.
+    /// The most specific matching rule wins over the others, and the options
+    /// it sets override the ones above; a rule with `epsilon` and no
+    /// `toleranceMode` is absolute
+    pub epsilon_rules: Option<Vec<JsEpsilonRule>>,
+
     /// Key to use for array element identification
     pub array_id_key: Option<String>,
 
@@ -20,22 +80,210 @@ pub struct JsDiffOptions {
     /// Only show differences in paths containing this string
     pub path_filter: Option<String>,
 
-    /// Output format
+    /// Only show differences at or below paths matching one of these globs,
+    /// e.g. `encoder.layers.*.attn.**`. `*` matches within one key and `**`
+    /// across keys; tensors of safetensors files are under `tensors`, and
+    /// results of directory comparisons are prefixed with `file/`
+    pub include: Option<Vec<String>>,
+
+    /// Hide differences at or below paths matching one of these globs, e.g.
+    /// `**.num_batches_tracked`; excluded tensors and files are not read. A
+    /// glob starting with `!` keeps matching paths (and then everything is
+    /// read), while one starting with `!` in `include` excludes them
+    pub exclude: Option<Vec<String>>,
+
+    /// Only show differences of these types
+    #[napi(ts_type = "Array<`${DiffType}`>")]
+    pub only_types: Option<Vec<String>>,
+
+    /// Hide differences of these types
+    #[napi(ts_type = "Array<`${DiffType}`>")]
+    pub exclude_types: Option<Vec<String>>,
+
+    /// Severity of some diff types, overriding the defaults: "critical" for
+    /// shape, architecture and activation function changes and tensors that
+    /// gained NaN or infinite values, "info" for
+    /// `Modified`, `TensorDataChanged`, `LossChange` and `AccuracyChange`, and
+    /// "warning" for the others
+    #[napi(ts_type = "Partial<Record<`${DiffType}`, `${Severity}`>>")]
+    pub severities: Option<HashMap<String, String>>,
+
+    /// Hide differences less severe than this
+    #[napi(ts_type = "`${Severity}`")]
+    pub min_severity: Option<String>,
+
+    /// Output format ("diffai", "json", "yaml", "ndjson", "html" or "markdown")
     pub output_format: Option<String>,
+
+    /// Model format of the inputs to `diffBuffers` ("safetensors" or "pytorch");
+    /// detected from the content if omitted
+    #[napi(ts_type = "'safetensors' | 'pytorch' | 'pt' | 'pth'")]
+    pub format: Option<String>,
+
+    /// Called with throttled progress updates while comparing paths
+    #[napi(ts_type = "(progress: JsDiffProgress) => void")]
+    pub on_progress: Option<ProgressCallback>,
+
+    /// Maximum number of results per batch yielded by `diffStream` and `diffPathsStream`
+    pub batch_size: Option<u32>,
+
+    /// Add norms, percentiles, the fraction of zeros and the number of NaN
+    /// and infinite values to the statistics of safetensors tensors (defaults
+    /// to false). Percentiles need a copy of each tensor's values
+    pub extended_stats: Option<bool>,
 }
 
 #[napi(object)]
+pub struct JsTensorDiffOptions {
+    /// Shape of both tensors (defaults to a flat `[length]`)
+    pub shape: Option<Vec<f64>>,
+
+    /// Shape of the old tensor, if different from `shape`
+    pub old_shape: Option<Vec<f64>>,
+
+    /// Shape of the new tensor, if different from `shape`
+    pub new_shape: Option<Vec<f64>>,
+
+    /// Data type reported in statistics (defaults to the TypedArray element type)
+    pub dtype: Option<String>,
+
+    /// Path reported in the results (defaults to "tensor")
+    pub path: Option<String>,
+
+    /// Element-wise tolerance under which the tensors are considered equal
+    pub epsilon: Option<f64>,
+
+    /// How elements are compared, like `toleranceMode` of `JsDiffOptions`
+    #[napi(ts_type = "'absolute' | 'relative' | 'numpy' | 'ulp'")]
+    pub tolerance_mode: Option<String>,
+
+    /// Relative tolerance of the "relative" and "numpy" modes
+    pub rtol: Option<f64>,
+
+    /// Maximum distance in units in the last place of the "ulp" mode
+    pub max_ulps: Option<f64>,
+
+    /// Add norms, percentiles and the fraction of zeros to the statistics
+    /// (defaults to false). Percentiles need a copy of the values
+    pub extended_stats: Option<bool>,
+}
+
+#[napi(object)]
+pub struct JsHtmlReportOptions {
+    /// Page title (defaults to "diffai report")
+    pub title: Option<String>,
+
+    /// Levels of the path tree that start expanded (defaults to 1)
+    pub expand_depth: Option<u32>,
+}
+
+#[napi(object)]
+pub struct JsMarkdownReportOptions {
+    /// Heading of the report (defaults to "diffai report")
+    pub title: Option<String>,
+
+    /// Number of leading path segments grouped into one section (defaults to
+    /// the parent path of each result, e.g. "fc1" for "fc1.weight")
+    pub group_depth: Option<u32>,
+
+    /// Characters shown of each old or new value (defaults to 80)
+    pub max_value_length: Option<u32>,
+
+    /// Rows shown per section (defaults to 50)
+    pub max_rows_per_group: Option<u32>,
+
+    /// Characters of the whole report (defaults to 65000, under GitHub's
+    /// comment limit); further sections are left out, and a first section
+    /// longer than that shows only the rows that fit
+    pub max_length: Option<u32>,
+}
+
+#[napi(object)]
+pub struct JsSummaryOptions {
+    /// Number of leading path segments grouped together (defaults to the
+    /// parent path of each result, e.g. "fc1" for "fc1.weight")
+    pub group_depth: Option<u32>,
+}
+
+#[napi(object)]
+pub struct JsRankOptions {
+    /// Number of tensors returned (defaults to 10)
+    pub k: Option<u32>,
+
+    /// Metric the tensors are ranked by (defaults to "meanShift")
+    #[napi(ts_type = "'meanShift' | 'stdRatio' | 'changeMagnitude' | 'relativeL2' | 'maxAbs'")]
+    pub by: Option<String>,
+}
+
+#[napi(object)]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct JsTensorStats {
     pub mean: f64,
     pub std: f64,
     pub min: f64,
     pub max: f64,
-    pub shape: Vec<u32>,
+    pub shape: Vec<f64>,
     pub dtype: String,
-    pub element_count: u32,
+    pub element_count: f64,
+
+    // Extended statistics, computed with the `extendedStats` option. The
+    // NaN and infinity counts are also given with `TensorNanIntroduced` and
+    // `TensorInfIntroduced` results. Norms and percentiles cover the finite
+    // values.
+    /// Sum of the absolute values
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub l1_norm: Option<f64>,
+
+    /// Square root of the sum of the squared values
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub l2_norm: Option<f64>,
+
+    /// 1st percentile
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub p1: Option<f64>,
+
+    /// Median
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub p50: Option<f64>,
+
+    /// 99th percentile
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub p99: Option<f64>,
+
+    /// Fraction of the elements that are zero
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub zero_fraction: Option<f64>,
+
+    /// Number of NaN elements
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nan_count: Option<f64>,
+
+    /// Number of infinite elements
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub inf_count: Option<f64>,
+
+    /// Histogram of the finite values, which HTML reports draw
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub histogram: Option<JsHistogram>,
+}
+
+#[napi(object)]
+#[derive(Serialize)]
+pub struct JsHistogram {
+    /// Smallest finite value, where the first bin starts
+    pub low: f64,
+
+    /// Largest finite value, where the last bin ends
+    pub high: f64,
+
+    /// Number of values in each of the equal bins between `low` and `high`
+    pub counts: Vec<f64>,
 }
 
 #[napi(object)]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct JsDiffResult {
     /// Type of difference
     pub diff_type: String,
@@ -44,49 +292,87 @@ pub struct JsDiffResult {
     pub path: String,
 
     /// Old value (for Modified/TypeChanged)
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub old_value: Option<serde_json::Value>,
 
     /// New value (for Modified/TypeChanged/Added)
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub new_value: Option<serde_json::Value>,
 
     /// Value (for Removed)
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub value: Option<serde_json::Value>,
 
     // AI/ML specific fields
     /// Old shape (for TensorShapeChanged)
-    pub old_shape: Option<Vec<u32>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old_shape: Option<Vec<f64>>,
 
     /// New shape (for TensorShapeChanged)
-    pub new_shape: Option<Vec<u32>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_shape: Option<Vec<f64>>,
 
     /// Old statistics (for TensorStatsChanged)
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub old_stats: Option<JsTensorStats>,
 
     /// New statistics (for TensorStatsChanged)
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub new_stats: Option<JsTensorStats>,
 
     /// Old mean (for TensorDataChanged)
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub old_mean: Option<f64>,
 
     /// New mean (for TensorDataChanged)
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub new_mean: Option<f64>,
 
     /// Change magnitude (for WeightSignificantChange)
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub change_magnitude: Option<f64>,
 
     /// Old string value (for architecture/activation/optimizer changes)
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub old_string: Option<String>,
 
     /// New string value (for architecture/activation/optimizer changes)
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub new_string: Option<String>,
 
     /// Old float value (for learning rate/loss/accuracy changes)
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub old_float: Option<f64>,
 
     /// New float value (for learning rate/loss/accuracy changes)
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub new_float: Option<f64>,
 }
 
+/// Every `diffType` a result can have
+#[napi(string_enum)]
+pub enum DiffType {
+    Added,
+    Removed,
+    Modified,
+    TypeChanged,
+    TensorShapeChanged,
+    TensorStatsChanged,
+    TensorDataChanged,
+    ModelArchitectureChanged,
+    WeightSignificantChange,
+    ActivationFunctionChanged,
+    LearningRateChanged,
+    OptimizerChanged,
+    LossChange,
+    AccuracyChange,
+    ModelVersionChanged,
+    /// A tensor gained NaN values
+    TensorNanIntroduced,
+    /// A tensor gained infinite values
+    TensorInfIntroduced,
+}
+
 /// Unified diff function for JavaScript/Node.js
 ///
 /// Compare two JavaScript objects or values and return differences.
@@ -111,21 +397,22 @@ pub struct JsDiffResult {
 /// const result = diff(oldModel, newModel);
 /// console.log(result);
 /// ```
-#[napi]
+#[napi(ts_return_type = "Array<DiffResult>")]
 pub fn diff(
+    env: Env,
     old: serde_json::Value,
     #[napi(ts_arg_type = "any")] new_value: serde_json::Value,
     options: Option<JsDiffOptions>,
 ) -> Result<Vec<JsDiffResult>> {
-    let rust_options = options.map(build_diff_options).transpose()?;
+    let rust_options = options.map(build_diff_options).transpose().into_js(&env)?;
 
-    let results = core_diff(&old, &new_value, rust_options.as_ref())
-        .map_err(|e| Error::new(Status::GenericFailure, format!("Diff error: {e}")))?;
+    let results = compare::diff(&old, &new_value, rust_options.as_ref()).into_js(&env)?;
 
     let js_results = results
         .into_iter()
         .map(convert_diff_result)
-        .collect::<Result<Vec<_>>>()?;
+        .collect::<DiffaiResult<Vec<_>>>()
+        .into_js(&env)?;
 
     Ok(js_results)
 }
@@ -141,65 +428,1060 @@ pub fn diff(
 /// # Returns
 ///
 /// Array of difference objects
-#[napi]
+#[napi(ts_return_type = "Array<DiffResult>")]
 pub fn diff_paths(
+    env: Env,
     old_path: String,
     new_path: String,
-    options: Option<JsDiffOptions>,
+    mut options: Option<JsDiffOptions>,
 ) -> Result<Vec<JsDiffResult>> {
-    let rust_options = options.map(build_diff_options).transpose()?;
+    let progress = ProgressReporter::new(options.as_mut().and_then(|o| o.on_progress.take()));
+    let rust_options = options.map(build_diff_options).transpose().into_js(&env)?;
 
-    let results = core_diff_paths(&old_path, &new_path, rust_options.as_ref())
-        .map_err(|e| Error::new(Status::GenericFailure, format!("Diff error: {e}")))?;
+    let results = Walker::new(rust_options.as_ref(), &CancelToken::default(), progress)
+        .diff_paths(&old_path, &new_path)
+        .into_js(&env)?;
 
     let js_results = results
         .into_iter()
         .map(convert_diff_result)
-        .collect::<Result<Vec<_>>>()?;
+        .collect::<DiffaiResult<Vec<_>>>()
+        .into_js(&env)?;
 
     Ok(js_results)
 }
 
+/// Compare two in-memory model files
+///
+/// # Arguments
+///
+/// * `old_buffer` - Contents of the old model file
+/// * `new_buffer` - Contents of the new model file
+/// * `options` - Optional configuration object; `format` selects the model
+///   format ("safetensors" or "pytorch") and is sniffed from the content when
+///   omitted. NumPy and MATLAB buffers are rejected, since their arrays are
+///   not read yet
+///
+/// # Returns
+///
+/// Array of difference objects
+///
+/// # Example
+///
+/// ```javascript
+/// const { diffBuffers } = require('diffai-js');
+///
+/// const result = diffBuffers(oldBuf, newBuf, { format: 'safetensors' });
+/// ```
+#[napi(ts_return_type = "Array<DiffResult>")]
+pub fn diff_buffers(
+    env: Env,
+    old_buffer: Buffer,
+    new_buffer: Buffer,
+    mut options: Option<JsDiffOptions>,
+) -> Result<Vec<JsDiffResult>> {
+    let format = options.as_mut().and_then(|o| o.format.take());
+    let rust_options = options.map(build_diff_options).transpose().into_js(&env)?;
+
+    let format = match format {
+        Some(name) => formats::parse_format_name(&name)
+            .map_err(|e| {
+                DiffaiError::new(ErrorCode::UnsupportedFormat, format!("Invalid format: {e}"))
+            })
+            .into_js(&env)?,
+        None => formats::sniff_format(&old_buffer)
+            .or_else(|| formats::sniff_format(&new_buffer))
+            .ok_or_else(|| {
+                DiffaiError::new(
+                    ErrorCode::UnsupportedFormat,
+                    "Unable to detect the model format from buffer contents; pass the format option",
+                )
+            })
+            .into_js(&env)?,
+    };
+    formats::check_readable(format).into_js(&env)?;
+
+    let extended = rust_options.as_ref().is_some_and(|o| o.extended_stats);
+    let old_model = parse_buffer(&old_buffer, format, extended, "old").into_js(&env)?;
+    let new_model = parse_buffer(&new_buffer, format, extended, "new").into_js(&env)?;
+
+    let mut results =
+        compare::diff_models(&old_model, &new_model, rust_options.as_ref()).into_js(&env)?;
+    if let Some(options) = &rust_options {
+        options.refine(&mut results);
+    }
+
+    let js_results = results
+        .into_iter()
+        .map(convert_diff_result)
+        .collect::<DiffaiResult<Vec<_>>>()
+        .into_js(&env)?;
+
+    Ok(js_results)
+}
+
+/// Compare two tensors stored in TypedArrays
+///
+/// The TypedArray memory is read in place, so large weight buffers are never
+/// converted to JSON numbers.
+///
+/// # Arguments
+///
+/// * `old_tensor` - Float32Array, Float64Array, Int8Array, Uint8Array or BigInt64Array
+/// * `new_tensor` - TypedArray holding the new values
+/// * `options` - Optional shape, dtype, path and tolerance
+///
+/// # Returns
+///
+/// Array with at most one `TensorShapeChanged`, `TensorStatsChanged`,
+/// `TensorDataChanged`, `TensorNanIntroduced` or `TensorInfIntroduced`
+/// result. With `extendedStats`, its statistics include norms, percentiles,
+/// the fraction of zeros and the number of NaN and infinite values.
+///
+/// # Example
+///
+/// ```javascript
+/// const { diffTensors } = require('diffai-js');
+///
+/// const result = diffTensors(oldWeights, newWeights, { shape: [768, 768], dtype: 'float32' });
+/// ```
+#[napi(ts_return_type = "Array<DiffResult>")]
+pub fn diff_tensors(
+    env: Env,
+    #[napi(ts_arg_type = "Float32Array | Float64Array | Int8Array | Uint8Array | BigInt64Array")]
+    old_tensor: JsTypedArray,
+    #[napi(ts_arg_type = "Float32Array | Float64Array | Int8Array | Uint8Array | BigInt64Array")]
+    new_tensor: JsTypedArray,
+    options: Option<JsTensorDiffOptions>,
+) -> Result<Vec<JsDiffResult>> {
+    let options = options.unwrap_or(JsTensorDiffOptions {
+        shape: None,
+        old_shape: None,
+        new_shape: None,
+        dtype: None,
+        path: None,
+        epsilon: None,
+        tolerance_mode: None,
+        rtol: None,
+        max_ulps: None,
+        extended_stats: None,
+    });
+    let margin = Margin::default()
+        .with(
+            options.epsilon,
+            options.tolerance_mode.as_deref(),
+            options.rtol,
+            options.max_ulps,
+        )
+        .into_js(&env)?;
+
+    let old_array = old_tensor.into_value()?;
+    let new_array = new_tensor.into_value()?;
+    let old_view = TensorView::new(&old_array).into_js(&env)?;
+    let new_view = TensorView::new(&new_array).into_js(&env)?;
+
+    let old_shape = tensor_shape(
+        options.old_shape.as_ref().or(options.shape.as_ref()),
+        &old_view,
+    )
+    .into_js(&env)?;
+    let new_shape = tensor_shape(
+        options.new_shape.as_ref().or(options.shape.as_ref()),
+        &new_view,
+    )
+    .into_js(&env)?;
+
+    let result = tensors::diff_tensors(
+        options.path.unwrap_or_else(|| "tensor".to_string()),
+        &old_view,
+        &new_view,
+        old_shape,
+        new_shape,
+        options.dtype,
+        &margin,
+        options.extended_stats.unwrap_or(false),
+    )
+    .map(convert_diff_result)
+    .transpose()
+    .into_js(&env)?;
+
+    Ok(result.into_iter().collect())
+}
+
+/// Asynchronous version of `diff`
+///
+/// Runs the comparison on the libuv threadpool and resolves with the same
+/// array of difference objects as `diff`.
+///
+/// # Example
+///
+/// ```javascript
+/// const { diffAsync } = require('diffai-js');
+///
+/// const result = await diffAsync(oldModel, newModel, { epsilon: 1e-6 });
+/// ```
+#[napi(ts_return_type = "Promise<Array<DiffResult>>")]
+pub fn diff_async(
+    env: Env,
+    old: serde_json::Value,
+    #[napi(ts_arg_type = "any")] new_value: serde_json::Value,
+    options: Option<JsDiffOptions>,
+) -> Result<AsyncTask<DiffTask>> {
+    let options = options.map(build_diff_options).transpose().into_js(&env)?;
+
+    Ok(AsyncTask::new(DiffTask {
+        old,
+        new_value,
+        options,
+    }))
+}
+
+/// Asynchronous version of `diffPaths`
+///
+/// Reading and comparing the files happens on the libuv threadpool, so large
+/// checkpoints do not block the event loop.
+///
+/// Passing an `AbortSignal` stops the comparison at the next file or tensor
+/// boundary, and the Promise rejects with an error whose `code` is
+/// `"DIFFAI_CANCELLED"`.
+///
+/// # Example
+///
+/// ```javascript
+/// const { diffPathsAsync } = require('diffai-js');
+///
+/// const controller = new AbortController();
+/// const result = await diffPathsAsync('model_v1.safetensors', 'model_v2.safetensors', {}, controller.signal);
+/// ```
+#[napi(ts_return_type = "Promise<Array<DiffResult>>")]
+pub fn diff_paths_async(
+    env: Env,
+    old_path: String,
+    new_path: String,
+    mut options: Option<JsDiffOptions>,
+    #[napi(ts_arg_type = "AbortSignal | undefined | null")] signal: Option<JsObject>,
+) -> Result<AsyncTask<DiffPathsTask>> {
+    let on_progress = options.as_mut().and_then(|o| o.on_progress.take());
+    let options = options.map(build_diff_options).transpose().into_js(&env)?;
+    let (cancel, abort_listener) = match signal {
+        Some(signal) => CancelToken::from_signal(&env, &signal)?,
+        None => (CancelToken::default(), None),
+    };
+
+    Ok(AsyncTask::new(DiffPathsTask {
+        old_path,
+        new_path,
+        options,
+        cancel,
+        abort_listener,
+        on_progress,
+    }))
+}
+
+/// Streaming version of `diff`
+///
+/// The comparison runs on a separate thread and the results are converted to
+/// JS objects one batch at a time, so the event loop is never blocked by a
+/// single huge array.
+///
+/// # Example
+///
+/// ```javascript
+/// const { diffStream } = require('diffai-js');
+///
+/// for await (const batch of diffStream(oldModel, newModel, { batchSize: 500 })) {
+///   batch.forEach(report);
+/// }
+/// ```
+#[napi(ts_return_type = "DiffStream")]
+pub fn diff_stream(
+    env: Env,
+    old: serde_json::Value,
+    #[napi(ts_arg_type = "any")] new_value: serde_json::Value,
+    options: Option<JsDiffOptions>,
+) -> Result<JsObject> {
+    let batch_size = batch_size(options.as_ref()).into_js(&env)?;
+    let options = options.map(build_diff_options).transpose().into_js(&env)?;
+
+    stream::spawn(
+        &env,
+        batch_size,
+        CancelToken::default(),
+        None,
+        move |batches| {
+            let results = compare::diff(&old, &new_value, options.as_ref())?;
+            batches.push(results)
+        },
+    )
+}
+
+/// Streaming version of `diffPaths`
+///
+/// Results are yielded in batches as each file is compared, so two large
+/// checkpoint directories never have to be held as one array. An `AbortSignal`
+/// stops the walk like it does for `diffPathsAsync`; leaving a `for await` loop
+/// early stops it too.
+///
+/// # Example
+///
+/// ```javascript
+/// const { diffPathsStream } = require('diffai-js');
+///
+/// for await (const batch of diffPathsStream('checkpoints/v1', 'checkpoints/v2')) {
+///   console.log(`${batch.length} differences`);
+/// }
+/// ```
+#[napi(ts_return_type = "DiffStream")]
+pub fn diff_paths_stream(
+    env: Env,
+    old_path: String,
+    new_path: String,
+    mut options: Option<JsDiffOptions>,
+    #[napi(ts_arg_type = "AbortSignal | undefined | null")] signal: Option<JsObject>,
+) -> Result<JsObject> {
+    let batch_size = batch_size(options.as_ref()).into_js(&env)?;
+    let on_progress = options.as_mut().and_then(|o| o.on_progress.take());
+    let options = options.map(build_diff_options).transpose().into_js(&env)?;
+    let (cancel, abort_listener) = match signal {
+        Some(signal) => CancelToken::from_signal(&env, &signal)?,
+        None => (CancelToken::default(), None),
+    };
+
+    let walker_cancel = cancel.clone();
+    stream::spawn(&env, batch_size, cancel, abort_listener, move |batches| {
+        let progress = ProgressReporter::new(on_progress);
+        Walker::new(options.as_ref(), &walker_cancel, progress).for_each_result(
+            &old_path,
+            &new_path,
+            &mut |results| batches.push(results),
+        )
+    })
+}
+
 /// Format diff results as string
 ///
 /// # Arguments
 ///
 /// * `results` - Array of diff results
-/// * `format` - Output format ("diffai", "json", "yaml")
+/// * `format` - Output format ("diffai", "json", "yaml", "ndjson", "html", "markdown")
 ///
 /// # Returns
 ///
 /// Formatted string output
 #[napi]
-pub fn format_output(results: Vec<JsDiffResult>, format: String) -> Result<String> {
+pub fn format_output(
+    env: Env,
+    #[napi(ts_arg_type = "Array<DiffResult | JsDiffResult>")] results: Vec<JsDiffResult>,
+    format: String,
+) -> Result<String> {
+    let rust_results = results
+        .into_iter()
+        .map(convert_js_diff_result)
+        .collect::<DiffaiResult<Vec<_>>>()
+        .into_js(&env)?;
+
+    let output_format = output::Format::parse(&format)
+        .map_err(|e| DiffaiError::new(ErrorCode::UnsupportedFormat, format!("Invalid format: {e}")))
+        .into_js(&env)?;
+
+    output::format_results(rust_results, output_format).into_js(&env)
+}
+
+/// Render results as a standalone HTML report
+///
+/// The page needs no external assets: results are grouped into a collapsible
+/// tree by path, colored by type, and `TensorStatsChanged` results show their
+/// old and new statistics side by side with a chart of the values. When both
+/// statistics have a `histogram`, computed from the tensor values with
+/// `extendedStats`, the chart draws both histograms over a shared axis.
+/// Otherwise only statistics are known, and it shows where the values lie:
+/// their 1st to 99th percentile if known, or else their mean ± std, within
+/// their min and max.
+///
+/// # Example
+///
+/// ```javascript
+/// const { diffPaths, renderHtmlReport } = require('diffai-js');
+/// const fs = require('fs');
+///
+/// const results = diffPaths('model_v1.safetensors', 'model_v2.safetensors');
+/// fs.writeFileSync('report.html', renderHtmlReport(results, { title: 'v1 vs v2' }));
+/// ```
+#[napi]
+pub fn render_html_report(
+    env: Env,
+    #[napi(ts_arg_type = "Array<DiffResult | JsDiffResult>")] results: Vec<JsDiffResult>,
+    options: Option<JsHtmlReportOptions>,
+) -> Result<String> {
+    let rust_results = results
+        .into_iter()
+        .map(convert_js_diff_result)
+        .collect::<DiffaiResult<Vec<_>>>()
+        .into_js(&env)?;
+
+    let mut html_options = html::HtmlOptions::default();
+    if let Some(options) = options {
+        if let Some(title) = options.title {
+            html_options.title = title;
+        }
+        if let Some(expand_depth) = options.expand_depth {
+            html_options.expand_depth = expand_depth as usize;
+        }
+    }
+
+    Ok(html::render(&rust_results, &html_options))
+}
+
+/// Render results as a Markdown report, e.g. for a pull request comment
+///
+/// The report starts with the number of results per diff type, followed by a
+/// collapsed `<details>` section per layer prefix listing old and new values.
+/// Long values, long sections and the report as a whole are truncated
+/// according to `options`.
+///
+/// # Example
+///
+/// ```javascript
+/// const { diffPaths, renderMarkdownReport } = require('diffai-js');
+///
+/// const results = diffPaths('model_v1.safetensors', 'model_v2.safetensors');
+/// const body = renderMarkdownReport(results, { maxLength: 60000 });
+/// ```
+#[napi]
+pub fn render_markdown_report(
+    env: Env,
+    #[napi(ts_arg_type = "Array<DiffResult | JsDiffResult>")] results: Vec<JsDiffResult>,
+    options: Option<JsMarkdownReportOptions>,
+) -> Result<String> {
+    let rust_results = results
+        .into_iter()
+        .map(convert_js_diff_result)
+        .collect::<DiffaiResult<Vec<_>>>()
+        .into_js(&env)?;
+
+    let mut markdown_options = markdown::MarkdownOptions::default();
+    if let Some(options) = options {
+        if let Some(title) = options.title {
+            markdown_options.title = title;
+        }
+        if let Some(group_depth) = options.group_depth {
+            markdown_options.group_depth = Some(group_depth as usize);
+        }
+        if let Some(max_value_length) = options.max_value_length {
+            markdown_options.max_value_length = max_value_length as usize;
+        }
+        if let Some(max_rows_per_group) = options.max_rows_per_group {
+            markdown_options.max_rows_per_group = max_rows_per_group as usize;
+        }
+        if let Some(max_length) = options.max_length {
+            markdown_options.max_length = max_length as usize;
+        }
+    }
+
+    Ok(markdown::render(&rust_results, &markdown_options))
+}
+
+/// Aggregate results per layer prefix
+///
+/// For each group of results sharing a path prefix, returns the number of
+/// results per diff type, the elements of the tensors whose statistics
+/// changed, and the mean and largest absolute change of the tensor means.
+/// Groups are returned in the order their first result appears.
+///
+/// # Example
+///
+/// ```javascript
+/// const { diff, summarize } = require('diffai-js');
+///
+/// for (const group of summarize(diff(oldModel, newModel), { groupDepth: 3 })) {
+///   console.log(group.prefix, group.parameters, group.maxAbsMeanShift);
+/// }
+/// ```
+#[napi]
+pub fn summarize(
+    env: Env,
+    #[napi(ts_arg_type = "Array<DiffResult | JsDiffResult>")] results: Vec<JsDiffResult>,
+    options: Option<JsSummaryOptions>,
+) -> Result<Vec<JsGroupSummary>> {
+    let rust_results = results
+        .into_iter()
+        .map(convert_js_diff_result)
+        .collect::<DiffaiResult<Vec<_>>>()
+        .into_js(&env)?;
+
+    let group_depth = options
+        .and_then(|options| options.group_depth)
+        .map(|depth| depth as usize);
+    Ok(summary::summarize(&rust_results, group_depth))
+}
+
+/// Rank the most changed tensors
+///
+/// Scores `TensorStatsChanged`, `TensorDataChanged` and
+/// `WeightSignificantChange` results by the metric given as `by`, skipping
+/// results it cannot be computed from:
+///
+/// * `meanShift` - absolute change of the mean
+/// * `stdRatio` - factor by which the standard deviation grew or shrank
+/// * `changeMagnitude` - magnitude of a `WeightSignificantChange`
+/// * `relativeL2` - change of the L2 norm relative to the old norm, using
+///   `l2Norm` of both statistics when given (see `extendedStats`) and an
+///   estimate from the mean, standard deviation and element count otherwise
+/// * `maxAbs` - change of the largest absolute value
+///
+/// Each tensor appears once, with its highest score. Tensors with the same
+/// score are ordered by path.
+///
+/// # Example
+///
+/// ```javascript
+/// const { diffPaths, rankChanges } = require('diffai-js');
+///
+/// const results = diffPaths('epoch1.safetensors', 'epoch2.safetensors');
+/// for (const { path, score } of rankChanges(results, { k: 5, by: 'relativeL2' })) {
+///   console.log(path, score);
+/// }
+/// ```
+#[napi]
+pub fn rank_changes(
+    env: Env,
+    #[napi(ts_arg_type = "Array<DiffResult | JsDiffResult>")] results: Vec<JsDiffResult>,
+    options: Option<JsRankOptions>,
+) -> Result<Vec<JsRankedChange>> {
     let rust_results = results
         .into_iter()
         .map(convert_js_diff_result)
-        .collect::<Result<Vec<_>>>()?;
+        .collect::<DiffaiResult<Vec<_>>>()
+        .into_js(&env)?;
+
+    let (k, by) = options.map_or((None, None), |options| (options.k, options.by));
+    let metric = by
+        .as_deref()
+        .map(Metric::parse)
+        .transpose()
+        .into_js(&env)?
+        .unwrap_or_default();
+
+    rank::rank(rust_results, metric, k.unwrap_or(10) as usize)
+        .into_iter()
+        .map(|(result, score)| {
+            Ok(JsRankedChange {
+                path: result.path().to_string(),
+                score,
+                result: convert_diff_result(result)?,
+            })
+        })
+        .collect::<DiffaiResult<Vec<_>>>()
+        .into_js(&env)
+}
+
+/// Convert diff results into an RFC 6902 JSON Patch
+///
+/// `Added`, `Removed`, `Modified` and `TypeChanged` results become `add`,
+/// `remove` and `replace` operations whose paths are JSON Pointers. Other
+/// result types are skipped. Results of a diff made with `arrayIdKey` cannot
+/// be converted, since their array elements have no index.
+///
+/// Pass the old value the results were computed from to keep object keys
+/// containing `.` or `[` whole; without it they are read as nested keys.
+///
+/// # Example
+///
+/// ```javascript
+/// const { diff, toJsonPatch } = require('diffai-js');
+///
+/// toJsonPatch(diff({ lr: 0.01, layers: [64] }, { lr: 0.001, layers: [64, 32] }));
+/// // [{ op: 'replace', path: '/lr', value: 0.001 }, { op: 'add', path: '/layers/1', value: 32 }]
+/// ```
+#[napi]
+pub fn to_json_patch(
+    env: Env,
+    #[napi(ts_arg_type = "Array<DiffResult | JsDiffResult>")] results: Vec<JsDiffResult>,
+    #[napi(ts_arg_type = "any")] old: Option<serde_json::Value>,
+) -> Result<Vec<JsPatchOperation>> {
+    let rust_results = results
+        .into_iter()
+        .map(|result| convert_js_diff_result(result).map(|entry| entry.result))
+        .collect::<DiffaiResult<Vec<_>>>()
+        .into_js(&env)?;
+
+    patch::to_operations(&rust_results, old.as_ref()).into_js(&env)
+}
+
+/// Apply a JSON Patch to a value and return the patched copy
+///
+/// `patch` is either an array of RFC 6902 operations or an array of diff
+/// results, which are converted as by `toJsonPatch`. Applying the results of
+/// `diff(old, new)` to `old` returns a value equal to `new`.
+///
+/// # Example
+///
+/// ```javascript
+/// const { applyPatch, diff } = require('diffai-js');
+///
+/// const delta = diff(oldConfig, newConfig);
+/// const rebuilt = applyPatch(oldConfig, delta); // deep-equals newConfig
+/// ```
+#[napi(ts_return_type = "any")]
+pub fn apply_patch(
+    env: Env,
+    #[napi(ts_arg_type = "any")] value: serde_json::Value,
+    #[napi(ts_arg_type = "Array<JsPatchOperation> | Array<DiffResult | JsDiffResult>")] patch: Vec<
+        JsObject,
+    >,
+) -> Result<serde_json::Value> {
+    let mut operations = Vec::new();
+    let mut results = Vec::new();
+    for entry in patch {
+        if entry.has_named_property("op")? {
+            operations.push(unsafe { JsPatchOperation::from_napi_value(env.raw(), entry.raw()) }?);
+        } else {
+            let result = unsafe { JsDiffResult::from_napi_value(env.raw(), entry.raw()) }?;
+            results.push(convert_js_diff_result(result).into_js(&env)?.result);
+        }
+    }
+
+    if !operations.is_empty() && !results.is_empty() {
+        return Err(DiffaiError::new(
+            ErrorCode::InvalidArgument,
+            "A patch cannot mix JSON Patch operations and diff results",
+        )
+        .into_napi(&env));
+    }
+    if !results.is_empty() {
+        operations = patch::to_operations(&results, Some(&value)).into_js(&env)?;
+    }
+
+    let mut value = value;
+    patch::apply(&mut value, &operations).into_js(&env)?;
+    Ok(value)
+}
+
+/// Apply diff results to a value and return the patched copy
+///
+/// Replays `Added`, `Removed`, `Modified` and `TypeChanged` results, so that
+/// `patch(old, diff(old, new))` equals `new`. Paths are resolved against the
+/// value, so results of a diff made with `arrayIdKey` find their array
+/// elements by id; added elements are appended to the array.
+///
+/// # Example
+///
+/// ```javascript
+/// const { diff, patch } = require('diffai-js');
+///
+/// const changes = diff(baseConfig, tunedConfig, { arrayIdKey: 'name' });
+/// const replayed = patch(otherConfig, changes);
+/// ```
+#[napi(ts_return_type = "any")]
+pub fn patch(
+    env: Env,
+    #[napi(ts_arg_type = "any")] old: serde_json::Value,
+    #[napi(ts_arg_type = "Array<DiffResult | JsDiffResult>")] results: Vec<JsDiffResult>,
+) -> Result<serde_json::Value> {
+    apply_results(env, old, results, patch::Direction::Forward)
+}
+
+/// Revert diff results on a value and return the reverted copy
+///
+/// The inverse of `patch`: `unpatch(new, diff(old, new))` equals `old`, except
+/// that array elements matched through `arrayIdKey` are restored at the end of
+/// their array.
+///
+/// # Example
+///
+/// ```javascript
+/// const { diff, unpatch } = require('diffai-js');
+///
+/// const changes = diff(baseConfig, tunedConfig);
+/// const reverted = unpatch(tunedConfig, changes); // deep-equals baseConfig
+/// ```
+#[napi(ts_return_type = "any")]
+pub fn unpatch(
+    env: Env,
+    #[napi(ts_arg_type = "any")] new_value: serde_json::Value,
+    #[napi(ts_arg_type = "Array<DiffResult | JsDiffResult>")] results: Vec<JsDiffResult>,
+) -> Result<serde_json::Value> {
+    apply_results(env, new_value, results, patch::Direction::Reverse)
+}
+
+/// Three-way merge of two values derived from a common base
+///
+/// The changes from `base` to `ours` and from `base` to `theirs` are found
+/// with `diff` and applied together. Locations that both sides changed in
+/// different ways are reported as conflicts and keep their base value.
+/// The tolerance options also decide whether two changed numbers agree, and
+/// `arrayIdKey` matches array elements such as layers by id.
+///
+/// # Example
+///
+/// ```javascript
+/// const { merge3 } = require('diffai-js');
+///
+/// const { merged, conflicts } = merge3(baseConfig, ourConfig, theirConfig, { arrayIdKey: 'name' });
+/// for (const { path, base, ours, theirs } of conflicts) {
+///   console.log(`${path}: ${base} -> ${ours} / ${theirs}`);
+/// }
+/// ```
+#[napi]
+pub fn merge3(
+    env: Env,
+    #[napi(ts_arg_type = "any")] base: serde_json::Value,
+    #[napi(ts_arg_type = "any")] ours: serde_json::Value,
+    #[napi(ts_arg_type = "any")] theirs: serde_json::Value,
+    options: Option<JsDiffOptions>,
+) -> Result<JsMergeResult> {
+    let rust_options = options.map(build_diff_options).transpose().into_js(&env)?;
+
+    merge::merge3(&base, &ours, &theirs, rust_options.as_ref()).into_js(&env)
+}
+
+/// Invert diff results so that they describe the change from new to old
+///
+/// `Added` becomes `Removed` and the other way round, and old and new values,
+/// shapes, statistics and means are swapped.
+///
+/// # Example
+///
+/// ```javascript
+/// const { diff, invert } = require('diffai-js');
+///
+/// invert(diff(oldModel, newModel)); // same as diff(newModel, oldModel)
+/// ```
+#[napi(ts_return_type = "Array<DiffResult>")]
+pub fn invert(
+    env: Env,
+    #[napi(ts_arg_type = "Array<DiffResult | JsDiffResult>")] results: Vec<JsDiffResult>,
+) -> Result<Vec<JsDiffResult>> {
+    results
+        .into_iter()
+        .map(|result| convert_js_diff_result(result).map(delta::invert))
+        .map(|result| result.and_then(convert_diff_result))
+        .collect::<DiffaiResult<Vec<_>>>()
+        .into_js(&env)
+}
 
-    let output_format = OutputFormat::parse_format(&format)
-        .map_err(|e| Error::new(Status::InvalidArg, format!("Invalid format: {e}")))?;
+/// Combine the results from A to B and from B to C into the results from A to C
+///
+/// Only the results are needed, not the values they were computed from.
+/// Changes to the same location are merged, and dropped when they cancel
+/// out. The magnitudes of `WeightSignificantChange` results are added, which
+/// gives an upper bound of the change from A to C.
+///
+/// # Example
+///
+/// ```javascript
+/// const { compose, diffPaths } = require('diffai-js');
+///
+/// let delta = diffPaths('epoch1.safetensors', 'epoch2.safetensors');
+/// delta = compose(delta, diffPaths('epoch2.safetensors', 'epoch3.safetensors'));
+/// ```
+#[napi(ts_return_type = "Array<DiffResult>")]
+pub fn compose(
+    env: Env,
+    #[napi(ts_arg_type = "Array<DiffResult | JsDiffResult>")] results_ab: Vec<JsDiffResult>,
+    #[napi(ts_arg_type = "Array<DiffResult | JsDiffResult>")] results_bc: Vec<JsDiffResult>,
+) -> Result<Vec<JsDiffResult>> {
+    let [ab, bc] = [results_ab, results_bc].map(|results| {
+        results
+            .into_iter()
+            .map(convert_js_diff_result)
+            .collect::<DiffaiResult<Vec<_>>>()
+    });
 
-    core_format_output(&rust_results, output_format)
-        .map_err(|e| Error::new(Status::GenericFailure, format!("Format error: {e}")))
+    delta::compose(ab.into_js(&env)?, bc.into_js(&env)?)
+        .and_then(|results| results.into_iter().map(convert_diff_result).collect())
+        .into_js(&env)
+}
+
+/// Reusable differ holding compiled options
+///
+/// The options are validated once when the `Differ` is constructed, so the
+/// `ignoreKeysRegex` pattern is not recompiled on every comparison.
+///
+/// # Example
+///
+/// ```javascript
+/// const { Differ } = require('diffai-js');
+///
+/// const differ = new Differ({ epsilon: 1e-6, ignoreKeysRegex: '^timestamp$', outputFormat: 'json' });
+/// for (const [oldModel, newModel] of pairs) {
+///   console.log(differ.format(differ.diff(oldModel, newModel)));
+/// }
+/// ```
+#[napi]
+pub struct Differ {
+    options: CompareOptions,
+    format: output::Format,
+    on_progress: Option<ProgressCallbackRef>,
+}
+
+#[napi]
+impl Differ {
+    #[napi(constructor)]
+    pub fn new(
+        env: Env,
+        #[napi(ts_arg_type = "JsDiffOptions | undefined | null")] options: Option<JsObject>,
+    ) -> Result<Self> {
+        let Some(options) = options else {
+            return Ok(Self {
+                options: CompareOptions::default(),
+                format: output::Format::default(),
+                on_progress: None,
+            });
+        };
+
+        // Keep a plain reference to the callback; a threadsafe function held
+        // for the lifetime of the Differ would keep the process alive
+        let on_progress = options
+            .get::<_, Option<ProgressCallbackRef>>("onProgress")?
+            .flatten();
+        let mut js_options = unsafe { JsDiffOptions::from_napi_value(env.raw(), options.raw()) }?;
+        js_options.on_progress = None;
+        let format = match &js_options.output_format {
+            Some(name) => parse_output_format(name).into_js(&env)?,
+            None => output::Format::default(),
+        };
+
+        Ok(Self {
+            options: build_diff_options(js_options).into_js(&env)?,
+            format,
+            on_progress,
+        })
+    }
+
+    /// Compare two JavaScript values, like `diff`
+    #[napi(ts_return_type = "Array<DiffResult>")]
+    pub fn diff(
+        &self,
+        env: Env,
+        old: serde_json::Value,
+        #[napi(ts_arg_type = "any")] new_value: serde_json::Value,
+    ) -> Result<Vec<JsDiffResult>> {
+        let results = compare::diff(&old, &new_value, Some(&self.options)).into_js(&env)?;
+
+        results
+            .into_iter()
+            .map(convert_diff_result)
+            .collect::<DiffaiResult<Vec<_>>>()
+            .into_js(&env)
+    }
+
+    /// Compare two files or directories, like `diffPaths`
+    #[napi(ts_return_type = "Array<DiffResult>")]
+    pub fn diff_paths(
+        &self,
+        env: Env,
+        old_path: String,
+        new_path: String,
+    ) -> Result<Vec<JsDiffResult>> {
+        let on_progress = self
+            .on_progress
+            .as_ref()
+            .map(|callback| progress::threadsafe_callback(&env, callback))
+            .transpose()?;
+        let progress = ProgressReporter::new(on_progress);
+
+        let results = Walker::new(Some(&self.options), &CancelToken::default(), progress)
+            .diff_paths(&old_path, &new_path)
+            .into_js(&env)?;
+
+        results
+            .into_iter()
+            .map(convert_diff_result)
+            .collect::<DiffaiResult<Vec<_>>>()
+            .into_js(&env)
+    }
+
+    /// Format results using the `outputFormat` option (defaults to "diffai")
+    #[napi]
+    pub fn format(
+        &self,
+        env: Env,
+        #[napi(ts_arg_type = "Array<DiffResult | JsDiffResult>")] results: Vec<JsDiffResult>,
+    ) -> Result<String> {
+        let rust_results = results
+            .into_iter()
+            .map(convert_js_diff_result)
+            .collect::<DiffaiResult<Vec<_>>>()
+            .into_js(&env)?;
+
+        output::format_results(rust_results, self.format).into_js(&env)
+    }
+}
+
+// Async tasks
+
+pub struct DiffTask {
+    old: serde_json::Value,
+    new_value: serde_json::Value,
+    options: Option<CompareOptions>,
+}
+
+#[napi]
+impl Task for DiffTask {
+    // Errors are carried in the output so that they can be turned into JS
+    // errors with an `Env` in `resolve`
+    type Output = DiffaiResult<Vec<DiffEntry>>;
+    type JsValue = Vec<JsDiffResult>;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        Ok(
+            compare::diff(&self.old, &self.new_value, self.options.as_ref())
+                .map_err(DiffaiError::from),
+        )
+    }
+
+    fn resolve(&mut self, env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        output
+            .and_then(|results| results.into_iter().map(convert_diff_result).collect())
+            .into_js(&env)
+    }
+}
+
+pub struct DiffPathsTask {
+    old_path: String,
+    new_path: String,
+    options: Option<CompareOptions>,
+    cancel: CancelToken,
+    abort_listener: Option<AbortListener>,
+    on_progress: Option<ProgressCallback>,
+}
+
+#[napi]
+impl Task for DiffPathsTask {
+    type Output = DiffaiResult<Vec<DiffEntry>>;
+    type JsValue = Vec<JsDiffResult>;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        let progress = ProgressReporter::new(self.on_progress.take());
+
+        Ok(Walker::new(self.options.as_ref(), &self.cancel, progress)
+            .diff_paths(&self.old_path, &self.new_path)
+            .map_err(DiffaiError::from))
+    }
+
+    fn resolve(&mut self, env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        output
+            .and_then(|results| results.into_iter().map(convert_diff_result).collect())
+            .into_js(&env)
+    }
+
+    fn finally(&mut self, env: Env) -> Result<()> {
+        match self.abort_listener.take() {
+            Some(listener) => listener.remove(env),
+            None => Ok(()),
+        }
+    }
 }
 
 // Helper functions
 
-fn build_diff_options(js_options: JsDiffOptions) -> Result<DiffOptions> {
-    let mut options = DiffOptions::default();
+fn apply_results(
+    env: Env,
+    mut value: serde_json::Value,
+    results: Vec<JsDiffResult>,
+    direction: patch::Direction,
+) -> Result<serde_json::Value> {
+    let rust_results = results
+        .into_iter()
+        .map(convert_js_diff_result)
+        .collect::<DiffaiResult<Vec<_>>>()
+        .into_js(&env)?;
 
-    if let Some(epsilon) = js_options.epsilon {
-        options.epsilon = Some(epsilon);
+    let rust_results = rust_results.iter().map(|entry| &entry.result);
+    patch::apply_results(&mut value, rust_results, direction).into_js(&env)?;
+    Ok(value)
+}
+
+fn parse_buffer(
+    bytes: &[u8],
+    format: diffai_core::FileFormat,
+    extended: bool,
+    which: &str,
+) -> DiffaiResult<tensors::Model> {
+    formats::parse_bytes(bytes, format, extended).map_err(|e| {
+        DiffaiError::new(
+            ErrorCode::ParseError,
+            format!("Failed to parse the {which} buffer: {e}"),
+        )
+        .with_cause(format!("{e:#}"))
+    })
+}
+
+/// Path of the element a result concerns
+fn result_path(result: &DiffResult) -> &str {
+    match result {
+        DiffResult::Added(path, _)
+        | DiffResult::Removed(path, _)
+        | DiffResult::Modified(path, _, _)
+        | DiffResult::TypeChanged(path, _, _)
+        | DiffResult::TensorShapeChanged(path, _, _)
+        | DiffResult::TensorStatsChanged(path, _, _)
+        | DiffResult::TensorDataChanged(path, _, _)
+        | DiffResult::ModelArchitectureChanged(path, _, _)
+        | DiffResult::WeightSignificantChange(path, _)
+        | DiffResult::ActivationFunctionChanged(path, _, _)
+        | DiffResult::LearningRateChanged(path, _, _)
+        | DiffResult::OptimizerChanged(path, _, _)
+        | DiffResult::LossChange(path, _, _)
+        | DiffResult::AccuracyChange(path, _, _)
+        | DiffResult::ModelVersionChanged(path, _, _) => path,
     }
+}
+
+/// `diffType` of a result, as in `JsDiffResult`
+fn diff_type_name(result: &DiffResult) -> &'static str {
+    match result {
+        DiffResult::Added(..) => "Added",
+        DiffResult::Removed(..) => "Removed",
+        DiffResult::Modified(..) => "Modified",
+        DiffResult::TypeChanged(..) => "TypeChanged",
+        DiffResult::TensorShapeChanged(..) => "TensorShapeChanged",
+        DiffResult::TensorStatsChanged(..) => "TensorStatsChanged",
+        DiffResult::TensorDataChanged(..) => "TensorDataChanged",
+        DiffResult::ModelArchitectureChanged(..) => "ModelArchitectureChanged",
+        DiffResult::WeightSignificantChange(..) => "WeightSignificantChange",
+        DiffResult::ActivationFunctionChanged(..) => "ActivationFunctionChanged",
+        DiffResult::LearningRateChanged(..) => "LearningRateChanged",
+        DiffResult::OptimizerChanged(..) => "OptimizerChanged",
+        DiffResult::LossChange(..) => "LossChange",
+        DiffResult::AccuracyChange(..) => "AccuracyChange",
+        DiffResult::ModelVersionChanged(..) => "ModelVersionChanged",
+    }
+}
+
+fn batch_size(options: Option<&JsDiffOptions>) -> DiffaiResult<usize> {
+    match options.and_then(|o| o.batch_size) {
+        None => Ok(stream::DEFAULT_BATCH_SIZE),
+        Some(0) => Err(DiffaiError::new(
+            ErrorCode::InvalidOption,
+            "Invalid batch size: must be at least 1",
+        )),
+        Some(size) => Ok(size as usize),
+    }
+}
+
+fn build_diff_options(js_options: JsDiffOptions) -> DiffaiResult<CompareOptions> {
+    let mut options = DiffOptions::default();
+
+    let margin = Margin::default().with(
+        js_options.epsilon,
+        js_options.tolerance_mode.as_deref(),
+        js_options.rtol,
+        js_options.max_ulps,
+    )?;
+    let tolerance = Tolerance::new(margin, js_options.epsilon_rules.unwrap_or_default())?;
+    // Tolerances the core cannot apply are applied to its results instead
+    options.epsilon = tolerance.core_epsilon();
 
     if let Some(array_id_key) = js_options.array_id_key {
         options.array_id_key = Some(array_id_key);
     }
 
     if let Some(ignore_keys_regex) = js_options.ignore_keys_regex {
-        let regex = Regex::new(&ignore_keys_regex)
-            .map_err(|e| Error::new(Status::InvalidArg, format!("Invalid regex: {e}")))?;
+        let regex = Regex::new(&ignore_keys_regex).map_err(|e| {
+            DiffaiError::new(ErrorCode::InvalidOption, format!("Invalid regex: {e}")).with_cause(e)
+        })?;
         options.ignore_keys_regex = Some(regex);
     }
 
@@ -208,28 +1490,187 @@ fn build_diff_options(js_options: JsDiffOptions) -> Result<DiffOptions> {
     }
 
     if let Some(output_format) = js_options.output_format {
-        let format = OutputFormat::parse_format(&output_format)
-            .map_err(|e| Error::new(Status::InvalidArg, format!("Invalid output format: {e}")))?;
-        options.output_format = Some(format);
+        // Formats the core does not know are handled here and leave it unset
+        if let output::Format::Core(format) = parse_output_format(&output_format)? {
+            options.output_format = Some(format);
+        }
     }
 
-    Ok(options)
+    let filter = PathFilter::new(
+        js_options.include.unwrap_or_default(),
+        js_options.exclude.unwrap_or_default(),
+    )?;
+
+    let types = TypeFilter::new(
+        js_options.only_types,
+        js_options.exclude_types.unwrap_or_default(),
+        js_options.severities.unwrap_or_default(),
+        js_options.min_severity,
+    )?;
+
+    Ok(CompareOptions {
+        core: options,
+        tolerance,
+        filter,
+        types,
+        extended_stats: js_options.extended_stats.unwrap_or(false),
+    })
 }
 
-fn convert_tensor_stats(stats: &TensorStats) -> JsTensorStats {
-    JsTensorStats {
+fn parse_output_format(name: &str) -> DiffaiResult<output::Format> {
+    output::Format::parse(name).map_err(|e| {
+        DiffaiError::new(
+            ErrorCode::InvalidOption,
+            format!("Invalid output format: {e}"),
+        )
+    })
+}
+
+fn tensor_shape(shape: Option<&Vec<f64>>, view: &TensorView) -> DiffaiResult<Vec<usize>> {
+    let Some(shape) = shape else {
+        return Ok(vec![view.len()]);
+    };
+
+    let shape = shape_from_js(shape)?;
+    let element_count = shape
+        .iter()
+        .try_fold(1usize, |count, &d| count.checked_mul(d))
+        .unwrap_or(usize::MAX);
+    if element_count != view.len() {
+        return Err(DiffaiError::new(
+            ErrorCode::InvalidArgument,
+            format!(
+                "Shape {shape:?} has {element_count} elements but the TypedArray has {}",
+                view.len()
+            ),
+        ));
+    }
+
+    Ok(shape)
+}
+
+/// Largest integer a JS number represents exactly (`Number.MAX_SAFE_INTEGER`).
+const MAX_SAFE_INTEGER: u64 = (1 << 53) - 1;
+
+/// Convert a tensor size to a JS number, refusing values that would lose precision.
+fn size_to_js(size: usize) -> DiffaiResult<f64> {
+    if size as u64 > MAX_SAFE_INTEGER {
+        return Err(DiffaiError::new(
+            ErrorCode::DiffError,
+            format!("Tensor size {size} exceeds Number.MAX_SAFE_INTEGER"),
+        ));
+    }
+    Ok(size as f64)
+}
+
+/// Convert a JS number back to a tensor size, rejecting anything that is not a
+/// non-negative safe integer.
+fn size_from_js(size: f64) -> DiffaiResult<usize> {
+    if !(size >= 0.0 && size.fract() == 0.0 && size <= MAX_SAFE_INTEGER as f64) {
+        return Err(DiffaiError::new(
+            ErrorCode::InvalidArgument,
+            format!("Invalid tensor size {size}: expected a non-negative safe integer"),
+        ));
+    }
+    usize::try_from(size as u64).map_err(|_| {
+        DiffaiError::new(
+            ErrorCode::InvalidArgument,
+            format!("Tensor size {size} is too large"),
+        )
+    })
+}
+
+fn shape_to_js(shape: &[usize]) -> DiffaiResult<Vec<f64>> {
+    shape.iter().map(|&d| size_to_js(d)).collect()
+}
+
+fn shape_from_js(shape: &[f64]) -> DiffaiResult<Vec<usize>> {
+    shape.iter().map(|&d| size_from_js(d)).collect()
+}
+
+fn convert_tensor_stats(
+    stats: &TensorStats,
+    extended: Option<&ExtendedStats>,
+) -> DiffaiResult<JsTensorStats> {
+    let extended = extended.cloned().unwrap_or_default();
+    let count = |count: Option<usize>| count.map(size_to_js).transpose();
+    Ok(JsTensorStats {
         mean: stats.mean,
         std: stats.std,
         min: stats.min,
         max: stats.max,
-        shape: stats.shape.iter().map(|&s| s as u32).collect(),
+        shape: shape_to_js(&stats.shape)?,
         dtype: stats.dtype.clone(),
-        element_count: stats.element_count as u32,
-    }
+        element_count: size_to_js(stats.element_count)?,
+        l1_norm: extended.l1_norm,
+        l2_norm: extended.l2_norm,
+        p1: extended.p1,
+        p50: extended.p50,
+        p99: extended.p99,
+        zero_fraction: extended.zero_fraction,
+        nan_count: count(extended.nan_count)?,
+        inf_count: count(extended.inf_count)?,
+        histogram: extended.histogram.map(convert_histogram).transpose()?,
+    })
 }
 
-fn convert_diff_result(result: DiffResult) -> Result<JsDiffResult> {
-    match result {
+fn convert_js_tensor_stats(stats: JsTensorStats) -> DiffaiResult<(TensorStats, ExtendedStats)> {
+    let count = |count: Option<f64>| count.map(size_from_js).transpose();
+    let extended = ExtendedStats {
+        l1_norm: stats.l1_norm,
+        l2_norm: stats.l2_norm,
+        p1: stats.p1,
+        p50: stats.p50,
+        p99: stats.p99,
+        zero_fraction: stats.zero_fraction,
+        nan_count: count(stats.nan_count)?,
+        inf_count: count(stats.inf_count)?,
+        histogram: stats.histogram.map(convert_js_histogram).transpose()?,
+    };
+    let stats = TensorStats {
+        mean: stats.mean,
+        std: stats.std,
+        min: stats.min,
+        max: stats.max,
+        shape: shape_from_js(&stats.shape)?,
+        dtype: stats.dtype,
+        element_count: size_from_js(stats.element_count)?,
+    };
+    Ok((stats, extended))
+}
+
+fn convert_histogram(histogram: Histogram) -> DiffaiResult<JsHistogram> {
+    Ok(JsHistogram {
+        low: histogram.low,
+        high: histogram.high,
+        counts: histogram
+            .counts
+            .into_iter()
+            .map(size_to_js)
+            .collect::<DiffaiResult<_>>()?,
+    })
+}
+
+fn convert_js_histogram(histogram: JsHistogram) -> DiffaiResult<Histogram> {
+    Ok(Histogram {
+        low: histogram.low,
+        high: histogram.high,
+        counts: histogram
+            .counts
+            .into_iter()
+            .map(size_from_js)
+            .collect::<DiffaiResult<_>>()?,
+    })
+}
+
+fn convert_diff_result(entry: DiffEntry) -> DiffaiResult<JsDiffResult> {
+    let diff_type = entry.diff_type();
+    let extended = entry.extended.map(|extended| *extended);
+    let (old_extended, new_extended) = match &extended {
+        Some((old, new)) => (Some(old), Some(new)),
+        None => (None, None),
+    };
+    match entry.result {
         DiffResult::Added(path, value) => Ok(JsDiffResult {
             diff_type: "Added".to_string(),
             path,
@@ -308,8 +1749,8 @@ fn convert_diff_result(result: DiffResult) -> Result<JsDiffResult> {
             old_value: None,
             new_value: None,
             value: None,
-            old_shape: Some(old_shape.iter().map(|&s| s as u32).collect()),
-            new_shape: Some(new_shape.iter().map(|&s| s as u32).collect()),
+            old_shape: Some(shape_to_js(&old_shape)?),
+            new_shape: Some(shape_to_js(&new_shape)?),
             old_stats: None,
             new_stats: None,
             old_mean: None,
@@ -321,15 +1762,15 @@ fn convert_diff_result(result: DiffResult) -> Result<JsDiffResult> {
             new_float: None,
         }),
         DiffResult::TensorStatsChanged(path, old_stats, new_stats) => Ok(JsDiffResult {
-            diff_type: "TensorStatsChanged".to_string(),
+            diff_type: diff_type.to_string(),
             path,
             old_value: None,
             new_value: None,
             value: None,
             old_shape: None,
             new_shape: None,
-            old_stats: Some(convert_tensor_stats(&old_stats)),
-            new_stats: Some(convert_tensor_stats(&new_stats)),
+            old_stats: Some(convert_tensor_stats(&old_stats, old_extended)?),
+            new_stats: Some(convert_tensor_stats(&new_stats, new_extended)?),
             old_mean: None,
             new_mean: None,
             change_magnitude: None,
@@ -503,35 +1944,81 @@ fn convert_diff_result(result: DiffResult) -> Result<JsDiffResult> {
     }
 }
 
-fn convert_js_diff_result(js_result: JsDiffResult) -> Result<DiffResult> {
+fn convert_js_diff_result(js_result: JsDiffResult) -> DiffaiResult<DiffEntry> {
+    match js_result.diff_type.as_str() {
+        // `diffai_core` has no counterpart of the results that report
+        // non-finite values, so they are handled as the stats change they are
+        "TensorStatsChanged" | "TensorNanIntroduced" | "TensorInfIntroduced" => {
+            let diff_type = js_result.diff_type.as_str();
+            let old_stats = js_result.old_stats.ok_or_else(|| {
+                DiffaiError::new(
+                    ErrorCode::InvalidArgument,
+                    format!("{diff_type} result must have old_stats"),
+                )
+            })?;
+            let new_stats = js_result.new_stats.ok_or_else(|| {
+                DiffaiError::new(
+                    ErrorCode::InvalidArgument,
+                    format!("{diff_type} result must have new_stats"),
+                )
+            })?;
+            let (old_stats, old_extended) = convert_js_tensor_stats(old_stats)?;
+            let (new_stats, new_extended) = convert_js_tensor_stats(new_stats)?;
+            Ok(DiffEntry {
+                result: DiffResult::TensorStatsChanged(js_result.path, old_stats, new_stats),
+                introduced: Introduced::parse(diff_type),
+                extended: (!old_extended.is_empty() || !new_extended.is_empty())
+                    .then(|| Box::new((old_extended, new_extended))),
+            })
+        }
+        _ => convert_js_core_result(js_result).map(DiffEntry::from),
+    }
+}
+
+fn convert_js_core_result(js_result: JsDiffResult) -> DiffaiResult<DiffResult> {
     match js_result.diff_type.as_str() {
         "Added" => {
             let value = js_result.new_value.ok_or_else(|| {
-                Error::new(Status::InvalidArg, "Added result must have new_value")
+                DiffaiError::new(
+                    ErrorCode::InvalidArgument,
+                    "Added result must have new_value",
+                )
             })?;
             Ok(DiffResult::Added(js_result.path, value))
         }
         "Removed" => {
-            let value = js_result
-                .value
-                .ok_or_else(|| Error::new(Status::InvalidArg, "Removed result must have value"))?;
+            let value = js_result.value.ok_or_else(|| {
+                DiffaiError::new(ErrorCode::InvalidArgument, "Removed result must have value")
+            })?;
             Ok(DiffResult::Removed(js_result.path, value))
         }
         "Modified" => {
             let old_value = js_result.old_value.ok_or_else(|| {
-                Error::new(Status::InvalidArg, "Modified result must have old_value")
+                DiffaiError::new(
+                    ErrorCode::InvalidArgument,
+                    "Modified result must have old_value",
+                )
             })?;
             let new_value = js_result.new_value.ok_or_else(|| {
-                Error::new(Status::InvalidArg, "Modified result must have new_value")
+                DiffaiError::new(
+                    ErrorCode::InvalidArgument,
+                    "Modified result must have new_value",
+                )
             })?;
             Ok(DiffResult::Modified(js_result.path, old_value, new_value))
         }
         "TypeChanged" => {
             let old_value = js_result.old_value.ok_or_else(|| {
-                Error::new(Status::InvalidArg, "TypeChanged result must have old_value")
+                DiffaiError::new(
+                    ErrorCode::InvalidArgument,
+                    "TypeChanged result must have old_value",
+                )
             })?;
             let new_value = js_result.new_value.ok_or_else(|| {
-                Error::new(Status::InvalidArg, "TypeChanged result must have new_value")
+                DiffaiError::new(
+                    ErrorCode::InvalidArgument,
+                    "TypeChanged result must have new_value",
+                )
             })?;
             Ok(DiffResult::TypeChanged(
                 js_result.path,
@@ -541,33 +2028,33 @@ fn convert_js_diff_result(js_result: JsDiffResult) -> Result<DiffResult> {
         }
         "TensorShapeChanged" => {
             let old_shape = js_result.old_shape.ok_or_else(|| {
-                Error::new(
-                    Status::InvalidArg,
+                DiffaiError::new(
+                    ErrorCode::InvalidArgument,
                     "TensorShapeChanged result must have old_shape",
                 )
             })?;
             let new_shape = js_result.new_shape.ok_or_else(|| {
-                Error::new(
-                    Status::InvalidArg,
+                DiffaiError::new(
+                    ErrorCode::InvalidArgument,
                     "TensorShapeChanged result must have new_shape",
                 )
             })?;
             Ok(DiffResult::TensorShapeChanged(
                 js_result.path,
-                old_shape.iter().map(|&s| s as usize).collect(),
-                new_shape.iter().map(|&s| s as usize).collect(),
+                shape_from_js(&old_shape)?,
+                shape_from_js(&new_shape)?,
             ))
         }
         "TensorDataChanged" => {
             let old_mean = js_result.old_mean.ok_or_else(|| {
-                Error::new(
-                    Status::InvalidArg,
+                DiffaiError::new(
+                    ErrorCode::InvalidArgument,
                     "TensorDataChanged result must have old_mean",
                 )
             })?;
             let new_mean = js_result.new_mean.ok_or_else(|| {
-                Error::new(
-                    Status::InvalidArg,
+                DiffaiError::new(
+                    ErrorCode::InvalidArgument,
                     "TensorDataChanged result must have new_mean",
                 )
             })?;
@@ -579,8 +2066,8 @@ fn convert_js_diff_result(js_result: JsDiffResult) -> Result<DiffResult> {
         }
         "WeightSignificantChange" => {
             let magnitude = js_result.change_magnitude.ok_or_else(|| {
-                Error::new(
-                    Status::InvalidArg,
+                DiffaiError::new(
+                    ErrorCode::InvalidArgument,
                     "WeightSignificantChange result must have change_magnitude",
                 )
             })?;
@@ -591,14 +2078,14 @@ fn convert_js_diff_result(js_result: JsDiffResult) -> Result<DiffResult> {
         }
         "LearningRateChanged" => {
             let old_lr = js_result.old_float.ok_or_else(|| {
-                Error::new(
-                    Status::InvalidArg,
+                DiffaiError::new(
+                    ErrorCode::InvalidArgument,
                     "LearningRateChanged result must have old_float",
                 )
             })?;
             let new_lr = js_result.new_float.ok_or_else(|| {
-                Error::new(
-                    Status::InvalidArg,
+                DiffaiError::new(
+                    ErrorCode::InvalidArgument,
                     "LearningRateChanged result must have new_float",
                 )
             })?;
@@ -610,30 +2097,112 @@ fn convert_js_diff_result(js_result: JsDiffResult) -> Result<DiffResult> {
         }
         "LossChange" => {
             let old_loss = js_result.old_float.ok_or_else(|| {
-                Error::new(Status::InvalidArg, "LossChange result must have old_float")
+                DiffaiError::new(
+                    ErrorCode::InvalidArgument,
+                    "LossChange result must have old_float",
+                )
             })?;
             let new_loss = js_result.new_float.ok_or_else(|| {
-                Error::new(Status::InvalidArg, "LossChange result must have new_float")
+                DiffaiError::new(
+                    ErrorCode::InvalidArgument,
+                    "LossChange result must have new_float",
+                )
             })?;
             Ok(DiffResult::LossChange(js_result.path, old_loss, new_loss))
         }
         "AccuracyChange" => {
             let old_acc = js_result.old_float.ok_or_else(|| {
-                Error::new(
-                    Status::InvalidArg,
+                DiffaiError::new(
+                    ErrorCode::InvalidArgument,
                     "AccuracyChange result must have old_float",
                 )
             })?;
             let new_acc = js_result.new_float.ok_or_else(|| {
-                Error::new(
-                    Status::InvalidArg,
+                DiffaiError::new(
+                    ErrorCode::InvalidArgument,
                     "AccuracyChange result must have new_float",
                 )
             })?;
             Ok(DiffResult::AccuracyChange(js_result.path, old_acc, new_acc))
         }
-        _ => Err(Error::new(
-            Status::InvalidArg,
+        "ModelArchitectureChanged" => {
+            let old_arch = js_result.old_string.ok_or_else(|| {
+                DiffaiError::new(
+                    ErrorCode::InvalidArgument,
+                    "ModelArchitectureChanged result must have old_string",
+                )
+            })?;
+            let new_arch = js_result.new_string.ok_or_else(|| {
+                DiffaiError::new(
+                    ErrorCode::InvalidArgument,
+                    "ModelArchitectureChanged result must have new_string",
+                )
+            })?;
+            Ok(DiffResult::ModelArchitectureChanged(
+                js_result.path,
+                old_arch,
+                new_arch,
+            ))
+        }
+        "ActivationFunctionChanged" => {
+            let old_fn = js_result.old_string.ok_or_else(|| {
+                DiffaiError::new(
+                    ErrorCode::InvalidArgument,
+                    "ActivationFunctionChanged result must have old_string",
+                )
+            })?;
+            let new_fn = js_result.new_string.ok_or_else(|| {
+                DiffaiError::new(
+                    ErrorCode::InvalidArgument,
+                    "ActivationFunctionChanged result must have new_string",
+                )
+            })?;
+            Ok(DiffResult::ActivationFunctionChanged(
+                js_result.path,
+                old_fn,
+                new_fn,
+            ))
+        }
+        "OptimizerChanged" => {
+            let old_opt = js_result.old_string.ok_or_else(|| {
+                DiffaiError::new(
+                    ErrorCode::InvalidArgument,
+                    "OptimizerChanged result must have old_string",
+                )
+            })?;
+            let new_opt = js_result.new_string.ok_or_else(|| {
+                DiffaiError::new(
+                    ErrorCode::InvalidArgument,
+                    "OptimizerChanged result must have new_string",
+                )
+            })?;
+            Ok(DiffResult::OptimizerChanged(
+                js_result.path,
+                old_opt,
+                new_opt,
+            ))
+        }
+        "ModelVersionChanged" => {
+            let old_ver = js_result.old_string.ok_or_else(|| {
+                DiffaiError::new(
+                    ErrorCode::InvalidArgument,
+                    "ModelVersionChanged result must have old_string",
+                )
+            })?;
+            let new_ver = js_result.new_string.ok_or_else(|| {
+                DiffaiError::new(
+                    ErrorCode::InvalidArgument,
+                    "ModelVersionChanged result must have new_string",
+                )
+            })?;
+            Ok(DiffResult::ModelVersionChanged(
+                js_result.path,
+                old_ver,
+                new_ver,
+            ))
+        }
+        _ => Err(DiffaiError::new(
+            ErrorCode::InvalidArgument,
             format!("Invalid diff result type: {}", js_result.diff_type),
         )),
     }
@@ -0,0 +1,263 @@
+use std::collections::HashMap;
+use std::fmt::{self, Write};
+
+use diffai_core::{DiffResult, TensorStats};
+
+use crate::entry::DiffEntry;
+use crate::html::{escape, number};
+use crate::keypath;
+use crate::tensors::ExtendedStats;
+
+/// Room kept for the truncation note when `max_length` is reached.
+const NOTE_RESERVE: usize = 200;
+
+/// Options of `render`
+pub(crate) struct MarkdownOptions {
+    pub(crate) title: String,
+    /// Leading path segments that form a group; `None` groups by parent path
+    pub(crate) group_depth: Option<usize>,
+    /// Characters shown of a single old or new value
+    pub(crate) max_value_length: usize,
+    /// Rows shown per group
+    pub(crate) max_rows_per_group: usize,
+    /// Characters of the whole report
+    pub(crate) max_length: usize,
+}
+
+impl Default for MarkdownOptions {
+    fn default() -> Self {
+        Self {
+            title: "diffai report".to_string(),
+            group_depth: None,
+            max_value_length: 80,
+            max_rows_per_group: 50,
+            // GitHub rejects comments longer than 65536 characters
+            max_length: 65_000,
+        }
+    }
+}
+
+/// Render a Markdown report with a summary table and one collapsed section
+/// per layer prefix.
+pub(crate) fn render(results: &[DiffEntry], options: &MarkdownOptions) -> String {
+    let mut out = String::new();
+    write_report(&mut out, results, options).expect("writing to a String cannot fail");
+    out
+}
+
+fn write_report(out: &mut String, results: &[DiffEntry], options: &MarkdownOptions) -> fmt::Result {
+    writeln!(out, "## {}\n", inline(&options.title))?;
+    write_summary(out, results)?;
+
+    let groups = group(results, options.group_depth);
+    let mut length = out.chars().count();
+    let fits = |length: usize, section: &str| {
+        length + section.chars().count() + NOTE_RESERVE <= options.max_length
+    };
+    for (shown, (prefix, members)) in groups.iter().enumerate() {
+        let rows = members.len().min(options.max_rows_per_group);
+        let full = section(prefix, members, rows, options);
+        if fits(length, &full) {
+            length += full.chars().count();
+            out.push_str(&full);
+            continue;
+        }
+
+        // Rather than no differences at all, as many rows of the first group
+        // as fit
+        let rows = match shown {
+            0 => (1..rows)
+                .collect::<Vec<_>>()
+                .partition_point(|&rows| fits(length, &section(prefix, members, rows, options))),
+            _ => 0,
+        };
+        let shown = match rows {
+            0 => shown,
+            _ => {
+                out.push_str(&section(prefix, members, rows, options));
+                shown + 1
+            }
+        };
+        if shown < groups.len() {
+            let hidden: usize = groups[shown..].iter().map(|(_, m)| m.len()).sum();
+            writeln!(
+                out,
+                "_Report truncated: {hidden} more differences in {} more groups are not shown._",
+                groups.len() - shown
+            )?;
+        }
+        break;
+    }
+
+    Ok(())
+}
+
+fn write_summary(out: &mut String, results: &[DiffEntry]) -> fmt::Result {
+    let mut counts: Vec<(&str, usize)> = Vec::new();
+    for result in results {
+        let name = result.diff_type();
+        match counts.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((name, 1)),
+        }
+    }
+
+    let noun = if results.len() == 1 {
+        "difference"
+    } else {
+        "differences"
+    };
+    writeln!(out, "**{} {noun}**\n", results.len())?;
+    if counts.is_empty() {
+        return Ok(());
+    }
+
+    out.write_str("| Diff type | Count |\n| --- | ---: |\n")?;
+    for (name, count) in counts {
+        writeln!(out, "| {name} | {count} |")?;
+    }
+    out.write_str("\n")
+}
+
+/// Group results by path prefix, keeping the order in which prefixes appear.
+pub(crate) fn group(results: &[DiffEntry], depth: Option<usize>) -> Vec<(String, Vec<&DiffEntry>)> {
+    let mut groups: Vec<(String, Vec<&DiffEntry>)> = Vec::new();
+    let mut index = HashMap::new();
+
+    for result in results {
+        let prefix = prefix(result.path(), depth);
+        let position = *index.entry(prefix.clone()).or_insert_with(|| {
+            groups.push((prefix, Vec::new()));
+            groups.len() - 1
+        });
+        groups[position].1.push(result);
+    }
+
+    groups
+}
+
+fn prefix(path: &str, depth: Option<usize>) -> String {
+    let segments = keypath::split(path);
+    let depth = depth.unwrap_or(segments.len().saturating_sub(1));
+    keypath::join(&segments[..depth.min(segments.len())])
+}
+
+/// The section of a group, showing its first `rows` results.
+fn section(prefix: &str, results: &[&DiffEntry], rows: usize, options: &MarkdownOptions) -> String {
+    let mut out = String::new();
+    write_group(&mut out, prefix, results, rows, options).expect("writing to a String cannot fail");
+    out
+}
+
+fn write_group(
+    out: &mut String,
+    prefix: &str,
+    results: &[&DiffEntry],
+    rows: usize,
+    options: &MarkdownOptions,
+) -> fmt::Result {
+    let name = if prefix.is_empty() { "(root)" } else { prefix };
+    writeln!(
+        out,
+        "<details>\n<summary><code>{}</code> ({})</summary>\n",
+        escape(name),
+        results.len()
+    )?;
+    out.write_str("| Path | Type | Old | New |\n| --- | --- | --- | --- |\n")?;
+
+    for result in results.iter().take(rows) {
+        let (old, new) = values(result);
+        writeln!(
+            out,
+            "| {} | {} | {} | {} |",
+            cell(result.path(), usize::MAX),
+            result.diff_type(),
+            cell(&old, options.max_value_length),
+            cell(&new, options.max_value_length)
+        )?;
+    }
+    if results.len() > rows {
+        writeln!(out, "| _{} more_ | | | |", results.len() - rows)?;
+    }
+
+    out.write_str("\n</details>\n\n")
+}
+
+/// The old and new side of a result as plain text.
+fn values(entry: &DiffEntry) -> (String, String) {
+    match &entry.result {
+        DiffResult::Added(_, value) => (String::new(), value.to_string()),
+        DiffResult::Removed(_, value) => (value.to_string(), String::new()),
+        DiffResult::Modified(_, old, new) | DiffResult::TypeChanged(_, old, new) => {
+            (old.to_string(), new.to_string())
+        }
+        DiffResult::TensorShapeChanged(_, old, new) => (format!("{old:?}"), format!("{new:?}")),
+        DiffResult::TensorStatsChanged(_, old, new) => {
+            let (old_extended, new_extended) = entry.extended();
+            (stats(old, old_extended), stats(new, new_extended))
+        }
+        DiffResult::TensorDataChanged(_, old, new) => (
+            format!("mean {}", number(*old)),
+            format!("mean {}", number(*new)),
+        ),
+        DiffResult::WeightSignificantChange(_, magnitude) => (
+            String::new(),
+            format!("change magnitude {}", number(*magnitude)),
+        ),
+        DiffResult::LearningRateChanged(_, old, new)
+        | DiffResult::LossChange(_, old, new)
+        | DiffResult::AccuracyChange(_, old, new) => (number(*old), number(*new)),
+        DiffResult::ModelArchitectureChanged(_, old, new)
+        | DiffResult::ActivationFunctionChanged(_, old, new)
+        | DiffResult::OptimizerChanged(_, old, new)
+        | DiffResult::ModelVersionChanged(_, old, new) => (old.clone(), new.clone()),
+    }
+}
+
+fn stats(stats: &TensorStats, extended: Option<&ExtendedStats>) -> String {
+    let mut text = format!(
+        "mean {}, std {}, min {}, max {}",
+        number(stats.mean),
+        number(stats.std),
+        number(stats.min),
+        number(stats.max)
+    );
+    for (count, values) in extended.map_or([(None, ""); 2], |extended| {
+        [
+            (extended.nan_count, "NaN"),
+            (extended.inf_count, "infinite"),
+        ]
+    }) {
+        if let Some(count) = count.filter(|&count| count > 0) {
+            text.push_str(&format!(", {count} {values}"));
+        }
+    }
+    text
+}
+
+/// A table cell: truncated, kept on one line and safe to embed in the table.
+fn cell(text: &str, max_length: usize) -> String {
+    if text.is_empty() {
+        return String::new();
+    }
+
+    let mut text = text.to_string();
+    if text.chars().count() > max_length {
+        text = text.chars().take(max_length.saturating_sub(1)).collect();
+        text.push('…');
+    }
+    format!("<code>{}</code>", inline(&text))
+}
+
+/// Text kept on one line and escaped, so that it cannot end a table cell or
+/// a heading, or start any other Markdown or HTML.
+fn inline(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '\n' | '\r' => " ".to_string(),
+            '|' => "&#124;".to_string(),
+            '#' => "&#35;".to_string(),
+            c => escape(c.encode_utf8(&mut [0; 4])),
+        })
+        .collect()
+}
@@ -0,0 +1,277 @@
+use std::collections::{HashMap, HashSet};
+
+use diffai_core::DiffResult;
+use napi_derive::napi;
+use serde_json::Value;
+
+use crate::compare::{self, CompareOptions};
+use crate::error::{DiffaiError, DiffaiResult};
+use crate::keypath::{self, Segment};
+use crate::patch::{self, Direction};
+use crate::result_path;
+use crate::tolerance::Margin;
+
+/// A location that `ours` and `theirs` changed in different ways
+#[napi(object)]
+pub struct JsMergeConflict {
+    /// Path of the location, like the `path` of a diff result
+    pub path: String,
+
+    /// Value in `base` (absent if the location did not exist there)
+    #[napi(ts_type = "any")]
+    pub base: Option<Value>,
+
+    /// Value in `ours` (absent if the location does not exist there)
+    #[napi(ts_type = "any")]
+    pub ours: Option<Value>,
+
+    /// Value in `theirs` (absent if the location does not exist there)
+    #[napi(ts_type = "any")]
+    pub theirs: Option<Value>,
+}
+
+/// Result of `merge3`
+#[napi(object)]
+pub struct JsMergeResult {
+    /// `base` with the changes of both sides; conflicting locations keep
+    /// their base value
+    #[napi(ts_type = "any")]
+    pub merged: Value,
+
+    /// Locations changed differently on both sides
+    pub conflicts: Vec<JsMergeConflict>,
+}
+
+/// One `Added`, `Removed`, `Modified` or `TypeChanged` result of one side.
+struct Change {
+    result: DiffResult,
+    segments: Vec<Segment>,
+}
+
+impl Change {
+    fn is_removal(&self) -> bool {
+        matches!(self.result, DiffResult::Removed(..))
+    }
+
+    /// Whether the change inserts or deletes an element of an array by index,
+    /// shifting the elements after it
+    fn shifts_array(&self) -> bool {
+        matches!(self.result, DiffResult::Added(..) | DiffResult::Removed(..))
+            && matches!(self.segments.last(), Some(Segment::Index(_)))
+    }
+
+    fn new_value(&self) -> Option<&Value> {
+        match &self.result {
+            DiffResult::Added(_, value)
+            | DiffResult::Modified(_, _, value)
+            | DiffResult::TypeChanged(_, _, value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+/// Merge the changes from `base` to `ours` and from `base` to `theirs`.
+///
+/// Changes are found with the diff engine, so the tolerance of each path
+/// decides both which numbers changed and whether two changed numbers agree,
+/// and `arrayIdKey` matches array elements by id.
+pub(crate) fn merge3(
+    base: &Value,
+    ours: &Value,
+    theirs: &Value,
+    options: Option<&CompareOptions>,
+) -> DiffaiResult<JsMergeResult> {
+    let margin_for = |change: &Change| {
+        options
+            .map(|o| o.tolerance.margin_for(result_path(&change.result)))
+            .unwrap_or_default()
+    };
+    let our_changes = changes(base, ours, options)?;
+    let their_changes = changes(base, theirs, options)?;
+    let ours_at = index(&our_changes);
+    let theirs_at = index(&their_changes);
+
+    let mut conflicts = Conflicts::default();
+    for (side, other, other_at) in [
+        (&our_changes, &their_changes, &theirs_at),
+        (&their_changes, &our_changes, &ours_at),
+    ] {
+        for change in side {
+            for len in 0..=change.segments.len() {
+                let Some(&position) = other_at.get(&change.segments[..len]) else {
+                    continue;
+                };
+                let other = &other[position];
+                let compatible = if len == change.segments.len() {
+                    same_change(change, other, &margin_for(change))
+                } else {
+                    // A removed value may also have lost some of its children
+                    change.is_removal() && other.is_removal()
+                };
+                if !compatible {
+                    let escalate = change.shifts_array() || other.shifts_array();
+                    conflicts.add(&change.segments[..len], escalate);
+                }
+            }
+        }
+    }
+
+    // Inserting on one side and deleting on the other makes indices disagree
+    let our_shifts = shifted_arrays(&our_changes);
+    let their_shifts = shifted_arrays(&their_changes);
+    for (inserted, deleted) in [
+        (&our_shifts.0, &their_shifts.1),
+        (&their_shifts.0, &our_shifts.1),
+    ] {
+        for parent in inserted.intersection(deleted) {
+            conflicts.add(parent, false);
+        }
+    }
+
+    let conflicts = conflicts.outermost();
+    let in_conflict = |change: &Change| {
+        conflicts
+            .iter()
+            .any(|conflict| change.segments.starts_with(conflict))
+    };
+    // A change is left out when the other side changed the same location the
+    // same way, or already replaced or removed one of its ancestors
+    let covered = |change: &Change, other_at: &HashMap<&[Segment], usize>, same: bool| {
+        let end = change.segments.len() + usize::from(same);
+        (0..end).any(|len| other_at.contains_key(&change.segments[..len]))
+    };
+
+    let accepted = our_changes
+        .iter()
+        .filter(|change| !in_conflict(change) && !covered(change, &theirs_at, false))
+        .chain(
+            their_changes
+                .iter()
+                .filter(|change| !in_conflict(change) && !covered(change, &ours_at, true)),
+        )
+        .map(|change| &change.result);
+
+    let mut merged = base.clone();
+    patch::apply_results(&mut merged, accepted, Direction::Forward)?;
+
+    let conflicts = conflicts
+        .iter()
+        .map(|segments| JsMergeConflict {
+            path: keypath::join(segments),
+            base: patch::lookup(base, segments).cloned(),
+            ours: patch::lookup(ours, segments).cloned(),
+            theirs: patch::lookup(theirs, segments).cloned(),
+        })
+        .collect();
+
+    Ok(JsMergeResult { merged, conflicts })
+}
+
+fn changes(
+    base: &Value,
+    other: &Value,
+    options: Option<&CompareOptions>,
+) -> DiffaiResult<Vec<Change>> {
+    let results = compare::diff(base, other, options).map_err(DiffaiError::from)?;
+    Ok(results
+        .into_iter()
+        .map(|entry| entry.result)
+        .filter(|result| {
+            matches!(
+                result,
+                DiffResult::Added(..)
+                    | DiffResult::Removed(..)
+                    | DiffResult::Modified(..)
+                    | DiffResult::TypeChanged(..)
+            )
+        })
+        .map(|result| Change {
+            segments: keypath::split(result_path(&result)),
+            result,
+        })
+        .collect())
+}
+
+fn index(changes: &[Change]) -> HashMap<&[Segment], usize> {
+    changes
+        .iter()
+        .enumerate()
+        .map(|(position, change)| (change.segments.as_slice(), position))
+        .collect()
+}
+
+/// Arrays whose elements a side inserts into and deletes from by index.
+fn shifted_arrays(changes: &[Change]) -> (HashSet<&[Segment]>, HashSet<&[Segment]>) {
+    let mut inserted = HashSet::new();
+    let mut deleted = HashSet::new();
+    for change in changes.iter().filter(|change| change.shifts_array()) {
+        let parent = &change.segments[..change.segments.len() - 1];
+        match change.is_removal() {
+            true => deleted.insert(parent),
+            false => inserted.insert(parent),
+        };
+    }
+    (inserted, deleted)
+}
+
+fn same_change(a: &Change, b: &Change, margin: &Margin) -> bool {
+    match (a.new_value(), b.new_value()) {
+        (Some(a), Some(b)) => values_match(a, b, margin),
+        (None, None) => a.is_removal() && b.is_removal(),
+        _ => false,
+    }
+}
+
+fn values_match(a: &Value, b: &Value, margin: &Margin) -> bool {
+    match (a, b) {
+        (Value::Number(x), Value::Number(y)) => match (x.as_f64(), y.as_f64()) {
+            (Some(x), Some(y)) => margin.allows(x, y),
+            _ => x == y,
+        },
+        (Value::Array(x), Value::Array(y)) => {
+            x.len() == y.len() && x.iter().zip(y).all(|(x, y)| values_match(x, y, margin))
+        }
+        (Value::Object(x), Value::Object(y)) => {
+            x.len() == y.len()
+                && x.iter()
+                    .all(|(key, x)| y.get(key).is_some_and(|y| values_match(x, y, margin)))
+        }
+        _ => a == b,
+    }
+}
+
+/// Conflicting locations in the order they were found.
+#[derive(Default)]
+struct Conflicts {
+    paths: Vec<Vec<Segment>>,
+    seen: HashSet<Vec<Segment>>,
+}
+
+impl Conflicts {
+    /// Record a conflict at `segments`, or at the array containing it when
+    /// `escalate` is set, since indices after an insertion or deletion no
+    /// longer line up.
+    fn add(&mut self, segments: &[Segment], escalate: bool) {
+        let segments = match (escalate, segments.split_last()) {
+            (true, Some((Segment::Index(_), parent))) => parent,
+            _ => segments,
+        };
+        if self.seen.insert(segments.to_vec()) {
+            self.paths.push(segments.to_vec());
+        }
+    }
+
+    /// Drop conflicts inside other conflicts.
+    fn outermost(self) -> Vec<Vec<Segment>> {
+        self.paths
+            .iter()
+            .filter(|path| {
+                !self
+                    .paths
+                    .iter()
+                    .any(|other| other.len() < path.len() && path.starts_with(other))
+            })
+            .cloned()
+            .collect()
+    }
+}
@@ -0,0 +1,195 @@
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+
+use diffai_core::{format_output as core_format_output, DiffResult, OutputFormat};
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+
+use crate::entry::DiffEntry;
+use crate::error::{DiffaiError, DiffaiResult, ErrorCode, IntoJsResult};
+use crate::{convert_diff_result, convert_js_diff_result, JsDiffResult};
+use crate::{html, markdown};
+
+/// The output formats of `diffai_core` plus those the core does not know.
+#[derive(Clone, Copy)]
+pub(crate) enum Format {
+    Core(OutputFormat),
+    /// One `JsDiffResult` object per line
+    Ndjson,
+    /// A standalone HTML report with default options
+    Html,
+    /// A Markdown report with default options
+    Markdown,
+}
+
+impl Format {
+    pub(crate) fn parse(name: &str) -> anyhow::Result<Self> {
+        match name.to_lowercase().as_str() {
+            "ndjson" | "jsonl" => Ok(Self::Ndjson),
+            "html" => Ok(Self::Html),
+            "markdown" | "md" => Ok(Self::Markdown),
+            _ => OutputFormat::parse_format(name).map(Self::Core),
+        }
+    }
+}
+
+impl Default for Format {
+    fn default() -> Self {
+        Self::Core(OutputFormat::default())
+    }
+}
+
+pub(crate) fn format_results(results: Vec<DiffEntry>, format: Format) -> DiffaiResult<String> {
+    match format {
+        Format::Core(format) => format_core(results, format).map_err(|e| {
+            DiffaiError::new(ErrorCode::DiffError, format!("Format error: {e}")).with_cause(e)
+        }),
+        Format::Ndjson => {
+            let mut output = Vec::new();
+            write_ndjson(&mut output, results).map_err(DiffaiError::from)?;
+            Ok(String::from_utf8(output).expect("serde_json writes UTF-8"))
+        }
+        Format::Html => Ok(html::render(&results, &html::HtmlOptions::default())),
+        Format::Markdown => Ok(markdown::render(
+            &results,
+            &markdown::MarkdownOptions::default(),
+        )),
+    }
+}
+
+/// Format results like `diffai_core::format_output`.
+///
+/// The JSON and YAML formats serialize the entries themselves, which keeps
+/// their `diffType` and extended statistics. The diffai format has no room for
+/// them and lists the tensors that gained NaN or infinite values at the end.
+fn format_core(results: Vec<DiffEntry>, format: OutputFormat) -> anyhow::Result<String> {
+    match format {
+        OutputFormat::Json => Ok(serde_json::to_string_pretty(&results)?),
+        OutputFormat::Yaml => Ok(serde_yaml::to_string(&results)?),
+        OutputFormat::Diffai => {
+            let (introduced, others): (Vec<_>, Vec<_>) = results
+                .into_iter()
+                .partition(|entry| entry.introduced.is_some());
+            let core_results: Vec<DiffResult> =
+                others.into_iter().map(|entry| entry.result).collect();
+            let mut output = core_format_output(&core_results, format)?;
+            for entry in &introduced {
+                if let Some(gain) = entry.gain() {
+                    if !output.is_empty() && !output.ends_with('\n') {
+                        output.push('\n');
+                    }
+                    output.push_str(&format!("  ~ {} {gain}\n", entry.path()));
+                }
+            }
+            Ok(output)
+        }
+    }
+}
+
+/// Write each result as a single line of JSON, using the `JsDiffResult` field names.
+fn write_ndjson<W: Write>(writer: &mut W, results: Vec<DiffEntry>) -> anyhow::Result<()> {
+    for result in results {
+        let result = convert_diff_result(result)?;
+        serde_json::to_writer(&mut *writer, &result)?;
+        writer.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+/// Appends results to a file as NDJSON
+///
+/// Each call to `write` serializes straight to the file, so the output of a
+/// large diff never has to be built as one string.
+///
+/// # Example
+///
+/// ```javascript
+/// const { NdjsonWriter, diffPathsStream } = require('diffai-js');
+///
+/// const writer = new NdjsonWriter('diff.ndjson');
+/// for await (const batch of diffPathsStream('checkpoints/v1', 'checkpoints/v2')) {
+///   writer.write(batch);
+/// }
+/// writer.close();
+/// ```
+#[napi]
+pub struct NdjsonWriter {
+    path: PathBuf,
+    writer: Option<BufWriter<File>>,
+}
+
+#[napi]
+impl NdjsonWriter {
+    /// Open `path` for appending, creating it if it does not exist
+    #[napi(constructor)]
+    pub fn new(env: Env, path: String) -> Result<Self> {
+        let path = PathBuf::from(path);
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| DiffaiError::write(&e, &path))
+            .into_js(&env)?;
+
+        Ok(Self {
+            path,
+            writer: Some(BufWriter::new(file)),
+        })
+    }
+
+    /// Append one line per result
+    #[napi]
+    pub fn write(
+        &mut self,
+        env: Env,
+        #[napi(ts_arg_type = "Array<DiffResult | JsDiffResult>")] results: Vec<JsDiffResult>,
+    ) -> Result<()> {
+        let results = results
+            .into_iter()
+            .map(convert_js_diff_result)
+            .collect::<DiffaiResult<Vec<_>>>()
+            .into_js(&env)?;
+
+        let writer = self.writer().into_js(&env)?;
+        write_ndjson(writer, results)
+            .map_err(|e| self.write_error(e))
+            .into_js(&env)
+    }
+
+    /// Write buffered lines to the file
+    #[napi]
+    pub fn flush(&mut self, env: Env) -> Result<()> {
+        let writer = self.writer().into_js(&env)?;
+        writer
+            .flush()
+            .map_err(|e| DiffaiError::write(&e, &self.path))
+            .into_js(&env)
+    }
+
+    /// Flush and close the file; further writes throw
+    #[napi]
+    pub fn close(&mut self, env: Env) -> Result<()> {
+        match self.writer.take() {
+            Some(mut writer) => writer
+                .flush()
+                .map_err(|e| DiffaiError::write(&e, &self.path))
+                .into_js(&env),
+            None => Ok(()),
+        }
+    }
+
+    fn writer(&mut self) -> DiffaiResult<&mut BufWriter<File>> {
+        self.writer.as_mut().ok_or_else(|| {
+            DiffaiError::new(ErrorCode::InvalidArgument, "NdjsonWriter is closed")
+                .with_path(&self.path)
+        })
+    }
+
+    fn write_error(&self, error: anyhow::Error) -> DiffaiError {
+        match error.downcast_ref::<std::io::Error>() {
+            Some(io_error) => DiffaiError::write(io_error, &self.path),
+            None => DiffaiError::from(error),
+        }
+    }
+}
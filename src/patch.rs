@@ -0,0 +1,586 @@
+use diffai_core::DiffResult;
+use napi_derive::napi;
+use serde_json::Value;
+
+use crate::error::{DiffaiError, DiffaiResult, ErrorCode};
+use crate::keypath::{self, Segment};
+
+/// One RFC 6902 JSON Patch operation
+#[napi(object)]
+pub struct JsPatchOperation {
+    #[napi(ts_type = "'add' | 'remove' | 'replace' | 'move' | 'copy' | 'test'")]
+    pub op: String,
+
+    /// JSON Pointer to the target location
+    pub path: String,
+
+    /// JSON Pointer to the source location (for move and copy)
+    pub from: Option<String>,
+
+    /// Value to add, replace with or test against
+    pub value: Option<Value>,
+}
+
+impl JsPatchOperation {
+    fn new(op: &str, path: String, value: Option<Value>) -> Self {
+        Self {
+            op: op.to_string(),
+            path,
+            from: None,
+            value,
+        }
+    }
+}
+
+/// Convert `Added`, `Removed`, `Modified` and `TypeChanged` results into JSON
+/// Patch operations. Other result types describe the model rather than edits
+/// to the value and are skipped.
+///
+/// Replacements come first, then removals with higher array indices before
+/// lower ones, then additions with lower array indices before higher ones, so
+/// that applying the operations in order turns the old value into the new one.
+///
+/// Result paths are resolved against `old` when given, so that object keys
+/// containing `.` or `[` are kept whole. Without it they are read as nested
+/// keys.
+pub(crate) fn to_operations(
+    results: &[DiffResult],
+    old: Option<&Value>,
+) -> DiffaiResult<Vec<JsPatchOperation>> {
+    let mut replaced = Vec::new();
+    let mut removed = Vec::new();
+    let mut added = Vec::new();
+
+    for result in results {
+        match result {
+            DiffResult::Added(path, value) => {
+                let steps = pointer_steps(path, old)?;
+                let operation =
+                    JsPatchOperation::new("add", join_pointer(&steps), Some(value.clone()));
+                added.push((pointer_position(&steps), operation));
+            }
+            DiffResult::Removed(path, _) => {
+                let steps = pointer_steps(path, old)?;
+                let operation = JsPatchOperation::new("remove", join_pointer(&steps), None);
+                removed.push((pointer_position(&steps), operation));
+            }
+            DiffResult::Modified(path, _, value) | DiffResult::TypeChanged(path, _, value) => {
+                let steps = pointer_steps(path, old)?;
+                replaced.push(JsPatchOperation::new(
+                    "replace",
+                    join_pointer(&steps),
+                    Some(value.clone()),
+                ));
+            }
+            _ => {}
+        }
+    }
+
+    removed.sort_by(|((parent_a, index_a), _), ((parent_b, index_b), _)| {
+        parent_a.cmp(parent_b).then(index_b.cmp(index_a))
+    });
+    added.sort_by(|(position_a, _), (position_b, _)| position_a.cmp(position_b));
+
+    Ok(replaced
+        .into_iter()
+        .chain(removed.into_iter().map(|(_, operation)| operation))
+        .chain(added.into_iter().map(|(_, operation)| operation))
+        .collect())
+}
+
+/// Resolve a result path such as `layers[0].weight` into the steps of a JSON
+/// Pointer, against `old` if given.
+fn pointer_steps(path: &str, old: Option<&Value>) -> DiffaiResult<Vec<Target>> {
+    let segments = keypath::split(path);
+    let steps = match old {
+        Some(old) if !segments.is_empty() => resolve(old, &segments).map_err(|reason| {
+            DiffaiError::new(
+                ErrorCode::InvalidArgument,
+                format!("Cannot convert '{path}' to a JSON Pointer: {reason}"),
+            )
+        })?,
+        _ => segments.into_iter().map(Target::from).collect(),
+    };
+    if steps.iter().any(|step| matches!(step, Target::Id(..))) {
+        return Err(DiffaiError::new(
+            ErrorCode::InvalidArgument,
+            format!(
+                "Cannot convert '{path}' to a JSON Pointer: elements matched by arrayIdKey have no index"
+            ),
+        ));
+    }
+    Ok(steps)
+}
+
+fn join_pointer(steps: &[Target]) -> String {
+    let mut pointer = String::new();
+    for step in steps {
+        pointer.push('/');
+        match step {
+            Target::Key(key) => pointer.push_str(&key.replace('~', "~0").replace('/', "~1")),
+            Target::Index(index) => pointer.push_str(&index.to_string()),
+            Target::Id(..) => unreachable!("rejected by pointer_steps"),
+        }
+    }
+    pointer
+}
+
+/// The pointer of the array an operation applies to and the index within it,
+/// or the pointer of the operation and `None` if it does not apply to an
+/// array index.
+fn pointer_position(steps: &[Target]) -> (String, Option<usize>) {
+    match steps.split_last() {
+        Some((Target::Index(index), parent)) => (join_pointer(parent), Some(*index)),
+        _ => (join_pointer(steps), None),
+    }
+}
+
+/// Apply JSON Patch operations to `value` in order, as described in RFC 6902.
+pub(crate) fn apply(value: &mut Value, operations: &[JsPatchOperation]) -> DiffaiResult<()> {
+    for (i, operation) in operations.iter().enumerate() {
+        apply_operation(value, operation).map_err(|reason| {
+            DiffaiError::new(
+                ErrorCode::InvalidArgument,
+                format!(
+                    "Cannot apply patch operation {i} ({} '{}'): {reason}",
+                    operation.op, operation.path
+                ),
+            )
+        })?;
+    }
+    Ok(())
+}
+
+fn apply_operation(document: &mut Value, operation: &JsPatchOperation) -> Result<(), String> {
+    let path = parse_pointer(&operation.path)?;
+    let value = || operation.value.clone().unwrap_or(Value::Null);
+    let from = || match &operation.from {
+        Some(from) => parse_pointer(from),
+        None => Err(format!("'{}' needs a 'from' pointer", operation.op)),
+    };
+
+    match operation.op.as_str() {
+        "add" => add(document, &path, value()),
+        "remove" => remove(document, &path).map(|_| ()),
+        "replace" => {
+            *get_mut(document, &path)? = value();
+            Ok(())
+        }
+        "move" => {
+            let from = from()?;
+            if path.len() > from.len() && path.starts_with(&from) {
+                return Err("cannot move a value into one of its children".to_string());
+            }
+            let moved = remove(document, &from)?;
+            add(document, &path, moved)
+        }
+        "copy" => {
+            let copied = get_mut(document, &from()?)?.clone();
+            add(document, &path, copied)
+        }
+        "test" => match *get_mut(document, &path)? == value() {
+            true => Ok(()),
+            false => Err("test failed".to_string()),
+        },
+        op => Err(format!("unknown operation '{op}'")),
+    }
+}
+
+fn parse_pointer(pointer: &str) -> Result<Vec<String>, String> {
+    if pointer.is_empty() {
+        return Ok(Vec::new());
+    }
+    let Some(rest) = pointer.strip_prefix('/') else {
+        return Err(format!("invalid JSON Pointer '{pointer}'"));
+    };
+    Ok(rest
+        .split('/')
+        .map(|token| token.replace("~1", "/").replace("~0", "~"))
+        .collect())
+}
+
+fn get_mut<'v>(document: &'v mut Value, path: &[String]) -> Result<&'v mut Value, String> {
+    let mut value = document;
+    for token in path {
+        value = match value {
+            Value::Object(map) => map.get_mut(token),
+            Value::Array(items) => array_index(token, items.len())
+                .ok()
+                .and_then(|index| items.get_mut(index)),
+            _ => None,
+        }
+        .ok_or_else(|| format!("'{token}' does not exist"))?;
+    }
+    Ok(value)
+}
+
+fn add(document: &mut Value, path: &[String], value: Value) -> Result<(), String> {
+    let Some((last, parent)) = path.split_last() else {
+        *document = value;
+        return Ok(());
+    };
+
+    match get_mut(document, parent)? {
+        Value::Object(map) => {
+            map.insert(last.clone(), value);
+        }
+        Value::Array(items) if last == "-" => items.push(value),
+        Value::Array(items) => {
+            let index = array_index(last, items.len() + 1)?;
+            items.insert(index, value);
+        }
+        _ => return Err("parent is not an object or array".to_string()),
+    }
+    Ok(())
+}
+
+fn remove(document: &mut Value, path: &[String]) -> Result<Value, String> {
+    let Some((last, parent)) = path.split_last() else {
+        return Err("cannot remove the whole document".to_string());
+    };
+
+    match get_mut(document, parent)? {
+        Value::Object(map) => map
+            .remove(last)
+            .ok_or_else(|| format!("'{last}' does not exist")),
+        Value::Array(items) => {
+            let index = array_index(last, items.len())?;
+            Ok(items.remove(index))
+        }
+        _ => Err("parent is not an object or array".to_string()),
+    }
+}
+
+/// Parse an array index token, which must be below `bound`.
+fn array_index(token: &str, bound: usize) -> Result<usize, String> {
+    let valid = !token.is_empty()
+        && token.bytes().all(|b| b.is_ascii_digit())
+        && (token == "0" || !token.starts_with('0'));
+    match token.parse::<usize>() {
+        Ok(index) if valid && index < bound => Ok(index),
+        _ => Err(format!("invalid array index '{token}'")),
+    }
+}
+
+/// Which side of the results `apply_results` produces
+#[derive(Clone, Copy)]
+pub(crate) enum Direction {
+    /// Turn the old value into the new one
+    Forward,
+    /// Turn the new value back into the old one
+    Reverse,
+}
+
+/// A change to one location, resolved from a result and a `Direction`.
+enum Edit {
+    Set(Value),
+    Delete,
+    Insert(Value),
+}
+
+/// Where an edit applies within its parent value
+enum Target {
+    Key(String),
+    Index(usize),
+    Id(String, String),
+}
+
+impl From<Segment> for Target {
+    fn from(segment: Segment) -> Self {
+        match segment {
+            Segment::Key(key) => Self::Key(key),
+            Segment::Index(index) => Self::Index(index),
+            Segment::Id(key, id) => Self::Id(key, id),
+        }
+    }
+}
+
+/// Apply `Added`, `Removed`, `Modified` and `TypeChanged` results to `value`,
+/// resolving result paths against the value itself.
+///
+/// Unlike JSON Pointers this handles elements matched through `arrayIdKey`,
+/// which are looked up by their id and appended when inserted, and object keys
+/// containing dots. Other result types are skipped.
+pub(crate) fn apply_results<'r>(
+    value: &mut Value,
+    results: impl IntoIterator<Item = &'r DiffResult>,
+    direction: Direction,
+) -> DiffaiResult<()> {
+    let mut sets = Vec::new();
+    let mut deletes = Vec::new();
+    let mut inserts = Vec::new();
+
+    for result in results {
+        let (path, edit) = match (result, direction) {
+            (DiffResult::Added(path, value), Direction::Forward)
+            | (DiffResult::Removed(path, value), Direction::Reverse) => {
+                (path, Edit::Insert(value.clone()))
+            }
+            (DiffResult::Added(path, _), Direction::Reverse)
+            | (DiffResult::Removed(path, _), Direction::Forward) => (path, Edit::Delete),
+            (DiffResult::Modified(path, old, new), _)
+            | (DiffResult::TypeChanged(path, old, new), _) => match direction {
+                Direction::Forward => (path, Edit::Set(new.clone())),
+                Direction::Reverse => (path, Edit::Set(old.clone())),
+            },
+            _ => continue,
+        };
+        let edits = match edit {
+            Edit::Set(_) => &mut sets,
+            Edit::Delete => &mut deletes,
+            Edit::Insert(_) => &mut inserts,
+        };
+        edits.push((result, keypath::split(path), edit));
+    }
+
+    // Deleting a lower index first would shift the elements after it, and
+    // inserting a higher index first could go past the end of the array
+    deletes.sort_by_key(|(_, segments, _)| {
+        let (parent, index) = array_position(segments);
+        (parent, std::cmp::Reverse(index))
+    });
+    inserts.sort_by_key(|(_, segments, _)| array_position(segments));
+
+    for (result, segments, edit) in sets.into_iter().chain(deletes).chain(inserts) {
+        apply_edit(value, &segments, edit).map_err(|reason| {
+            let action = match direction {
+                Direction::Forward => "apply",
+                Direction::Reverse => "revert",
+            };
+            DiffaiError::new(
+                ErrorCode::InvalidArgument,
+                format!(
+                    "Cannot {action} the {} result at '{}': {reason}",
+                    crate::diff_type_name(result),
+                    crate::result_path(result)
+                ),
+            )
+        })?;
+    }
+    Ok(())
+}
+
+fn apply_edit(document: &mut Value, segments: &[Segment], edit: Edit) -> Result<(), String> {
+    if segments.is_empty() {
+        return match edit {
+            Edit::Set(value) | Edit::Insert(value) => {
+                *document = value;
+                Ok(())
+            }
+            Edit::Delete => Err("cannot remove the whole value".to_string()),
+        };
+    }
+
+    let (parent, target) = locate(document, segments)?;
+    match (parent, target, edit) {
+        (Value::Object(map), Target::Key(key), Edit::Set(value)) => match map.get_mut(&key) {
+            Some(slot) => *slot = value,
+            None => return Err(format!("'{key}' does not exist")),
+        },
+        (Value::Object(map), Target::Key(key), Edit::Delete) => {
+            map.remove(&key)
+                .ok_or_else(|| format!("'{key}' does not exist"))?;
+        }
+        (Value::Object(map), Target::Key(key), Edit::Insert(value)) => {
+            map.insert(key, value);
+        }
+        (Value::Array(items), Target::Index(index), Edit::Set(value)) => match items.get_mut(index)
+        {
+            Some(slot) => *slot = value,
+            None => return Err(format!("index {index} is out of bounds")),
+        },
+        (Value::Array(items), Target::Index(index), Edit::Delete) if index < items.len() => {
+            items.remove(index);
+        }
+        (Value::Array(items), Target::Index(index), Edit::Insert(value))
+            if index <= items.len() =>
+        {
+            items.insert(index, value);
+        }
+        (Value::Array(_), Target::Index(index), _) => {
+            return Err(format!("index {index} is out of bounds"))
+        }
+        (Value::Array(items), Target::Id(key, id), Edit::Insert(value)) => {
+            if find_by_id(items, &key, &id).is_some() {
+                return Err(format!("an element with {key}={id} already exists"));
+            }
+            items.push(value);
+        }
+        (Value::Array(items), Target::Id(key, id), edit) => {
+            let index =
+                find_by_id(items, &key, &id).ok_or_else(|| format!("no element has {key}={id}"))?;
+            match edit {
+                Edit::Set(value) => items[index] = value,
+                _ => {
+                    items.remove(index);
+                }
+            }
+        }
+        (Value::Object(_), _, _) => return Err("expected an array".to_string()),
+        _ => return Err("expected an object".to_string()),
+    }
+    Ok(())
+}
+
+/// Walk to the parent of the last location in `segments`, as resolved by
+/// `resolve`.
+fn locate<'v>(
+    document: &'v mut Value,
+    segments: &[Segment],
+) -> Result<(&'v mut Value, Target), String> {
+    let mut steps = resolve(document, segments)?;
+    let target = steps.pop().expect("segments are not empty");
+
+    let mut current = document;
+    for step in steps {
+        current = match step {
+            Target::Key(key) => current.get_mut(&key),
+            Target::Index(index) => current.get_mut(index),
+            Target::Id(key, id) => current.as_array_mut().and_then(|items| {
+                let index = find_by_id(items, &key, &id)?;
+                items.get_mut(index)
+            }),
+        }
+        .expect("step was resolved above");
+    }
+    Ok((current, target))
+}
+
+/// Resolve `segments` against `document` into one step per nesting level.
+/// Every step but the last must exist.
+///
+/// Keys are matched greedily, so a path `a.b.c` finds the key `"a.b"` when the
+/// object has it.
+fn resolve(document: &Value, segments: &[Segment]) -> Result<Vec<Target>, String> {
+    let mut steps = Vec::new();
+    let mut current = document;
+    let mut i = 0;
+
+    while i < segments.len() {
+        let last = i + 1 == segments.len();
+        match &segments[i] {
+            Segment::Key(_) => {
+                let run_end = segments[i..]
+                    .iter()
+                    .position(|s| !matches!(s, Segment::Key(_)))
+                    .map_or(segments.len(), |offset| i + offset);
+                let Value::Object(map) = current else {
+                    return Err("expected an object".to_string());
+                };
+                let end = (i + 1..=run_end)
+                    .rev()
+                    .find(|&end| map.contains_key(&join_keys(&segments[i..end])));
+
+                match end {
+                    Some(end) if end < segments.len() => {
+                        let key = join_keys(&segments[i..end]);
+                        current = &map[&key];
+                        steps.push(Target::Key(key));
+                        i = end;
+                    }
+                    _ if run_end == segments.len() => {
+                        steps.push(Target::Key(join_keys(&segments[i..])));
+                        break;
+                    }
+                    _ => {
+                        return Err(format!(
+                            "'{}' does not exist",
+                            join_keys(&segments[i..run_end])
+                        ));
+                    }
+                }
+            }
+            Segment::Index(index) if last => {
+                steps.push(Target::Index(*index));
+                i += 1;
+            }
+            Segment::Index(index) => {
+                current = current
+                    .as_array()
+                    .and_then(|items| items.get(*index))
+                    .ok_or_else(|| format!("index {index} does not exist"))?;
+                steps.push(Target::Index(*index));
+                i += 1;
+            }
+            Segment::Id(key, id) if last => {
+                steps.push(Target::Id(key.clone(), id.clone()));
+                i += 1;
+            }
+            Segment::Id(key, id) => {
+                let items = current
+                    .as_array()
+                    .ok_or_else(|| "expected an array".to_string())?;
+                let index = find_by_id(items, key, id)
+                    .ok_or_else(|| format!("no element has {key}={id}"))?;
+                current = &items[index];
+                steps.push(Target::Id(key.clone(), id.clone()));
+                i += 1;
+            }
+        }
+    }
+    Ok(steps)
+}
+
+/// Look up the value at a result path, resolving keys and ids like
+/// `apply_results`.
+pub(crate) fn lookup<'v>(document: &'v Value, segments: &[Segment]) -> Option<&'v Value> {
+    let mut current = document;
+    let mut i = 0;
+
+    while i < segments.len() {
+        match &segments[i] {
+            Segment::Key(_) => {
+                let map = current.as_object()?;
+                let run_end = segments[i..]
+                    .iter()
+                    .position(|s| !matches!(s, Segment::Key(_)))
+                    .map_or(segments.len(), |offset| i + offset);
+                let (end, value) = (i + 1..=run_end)
+                    .rev()
+                    .find_map(|end| Some((end, map.get(&join_keys(&segments[i..end]))?)))?;
+                current = value;
+                i = end;
+            }
+            Segment::Index(index) => {
+                current = current.as_array()?.get(*index)?;
+                i += 1;
+            }
+            Segment::Id(key, id) => {
+                let items = current.as_array()?;
+                current = &items[find_by_id(items, key, id)?];
+                i += 1;
+            }
+        }
+    }
+    Some(current)
+}
+
+/// Position of the element whose `key` is `id`, written as JSON in result paths.
+fn find_by_id(items: &[Value], key: &str, id: &str) -> Option<usize> {
+    let id: Value = serde_json::from_str(id).ok()?;
+    items.iter().position(|item| item.get(key) == Some(&id))
+}
+
+fn join_keys(segments: &[Segment]) -> String {
+    segments
+        .iter()
+        .map(|segment| segment.to_string())
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// The path of the array an edit applies to and the index within it, or the
+/// path of the edit and `None` if it does not apply to an array index.
+fn array_position(segments: &[Segment]) -> (String, Option<usize>) {
+    match segments.split_last() {
+        Some((Segment::Index(index), parent)) => (join_path(parent), Some(*index)),
+        _ => (join_path(segments), None),
+    }
+}
+
+fn join_path(segments: &[Segment]) -> String {
+    segments
+        .iter()
+        .map(|segment| format!("/{segment}"))
+        .collect()
+}
@@ -0,0 +1,330 @@
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use diffai_core::{detect_format_from_path, parse_file_by_format, DiffResult, FileFormat};
+use serde_json::Value;
+
+use crate::cancel::{CancelToken, Cancelled};
+use crate::compare::{self, CompareOptions};
+use crate::entry::DiffEntry;
+use crate::error::{DiffaiError, ErrorCode};
+use crate::progress::ProgressReporter;
+use crate::safetensors::parse_safetensors;
+use crate::tensors::Model;
+
+/// Receives the results of one compared file (or one added or removed file).
+pub(crate) type ResultSink<'s> = dyn FnMut(Vec<DiffEntry>) -> Result<()> + 's;
+
+/// Compares two files or directories the same way `diffai_core::diff_paths` does.
+///
+/// The walk happens here rather than in the core so that cancellation can be
+/// checked and progress reported between files and, for safetensors, between
+/// tensors. Directory entries are visited in sorted order, which also makes the
+/// output stable.
+pub(crate) struct Walker<'a> {
+    options: Option<&'a CompareOptions>,
+    cancel: &'a CancelToken,
+    progress: ProgressReporter,
+}
+
+impl<'a> Walker<'a> {
+    pub(crate) fn new(
+        options: Option<&'a CompareOptions>,
+        cancel: &'a CancelToken,
+        progress: ProgressReporter,
+    ) -> Self {
+        Self {
+            options,
+            cancel,
+            progress,
+        }
+    }
+
+    pub(crate) fn diff_paths(self, old_path: &str, new_path: &str) -> Result<Vec<DiffEntry>> {
+        let mut results = Vec::new();
+        self.for_each_result(old_path, new_path, &mut |file_results| {
+            results.extend(file_results);
+            Ok(())
+        })?;
+
+        Ok(results)
+    }
+
+    /// Like `diff_paths`, but hands the results of each file to `sink` as soon
+    /// as the file has been compared instead of collecting them all.
+    pub(crate) fn for_each_result(
+        mut self,
+        old_path: &str,
+        new_path: &str,
+        sink: &mut ResultSink,
+    ) -> Result<()> {
+        let options = self.options;
+        let sink = &mut |mut results: Vec<DiffEntry>| {
+            if let Some(options) = options {
+                options.refine(&mut results);
+            }
+            sink(results)
+        };
+        let path1 = Path::new(old_path);
+        let path2 = Path::new(new_path);
+
+        self.cancel.check()?;
+
+        match (path1.is_dir(), path2.is_dir()) {
+            (true, true) => self.diff_directories(path1, path2, sink),
+            (false, false) => {
+                self.progress
+                    .set_totals(1, file_size(path1) + file_size(path2));
+                self.progress.begin_file(old_path);
+                let results = self.diff_files(path1, path2, None);
+                self.progress.end_file(file_size(path1) + file_size(path2));
+                sink(results?)
+            }
+            (true, false) => Err(DiffaiError::new(
+                ErrorCode::InvalidArgument,
+                format!("Cannot compare directory '{old_path}' with file '{new_path}'"),
+            )
+            .with_path(new_path)
+            .into()),
+            (false, true) => Err(DiffaiError::new(
+                ErrorCode::InvalidArgument,
+                format!("Cannot compare file '{old_path}' with directory '{new_path}'"),
+            )
+            .with_path(new_path)
+            .into()),
+        }?;
+
+        self.progress.finish();
+        Ok(())
+    }
+
+    /// Compare two files; `rel_path` is the path the results will be prefixed
+    /// with in a directory comparison.
+    fn diff_files(
+        &mut self,
+        path1: &Path,
+        path2: &Path,
+        rel_path: Option<&str>,
+    ) -> Result<Vec<DiffEntry>> {
+        let format1 = detect_format(path1)?;
+        let format2 = detect_format(path2)?;
+
+        if std::mem::discriminant(&format1) != std::mem::discriminant(&format2) {
+            return Err(DiffaiError::new(
+                ErrorCode::UnsupportedFormat,
+                format!("Cannot compare files with different formats: {format1:?} vs {format2:?}"),
+            )
+            .with_path(path2)
+            .into());
+        }
+
+        let tensors = match rel_path {
+            Some(rel_path) => format!("{rel_path}/tensors"),
+            None => "tensors".to_string(),
+        };
+        let model1 = self.parse_file(path1, format1, Some(&tensors))?;
+        let model2 = self.parse_file(path2, format2, Some(&tensors))?;
+
+        self.cancel.check()?;
+        compare::diff_models(&model1, &model2, self.options)
+    }
+
+    fn diff_directories(&mut self, dir1: &Path, dir2: &Path, sink: &mut ResultSink) -> Result<()> {
+        let files1 = model_files(dir1)?;
+        let files2 = model_files(dir2)?;
+
+        let mut entries: Vec<&String> = files1.keys().chain(files2.keys()).collect();
+        entries.sort();
+        entries.dedup();
+        let bytes_total = files1
+            .values()
+            .chain(files2.values())
+            .map(|p| file_size(p))
+            .sum();
+        self.progress.set_totals(entries.len(), bytes_total);
+
+        // Files that only exist in the old directory
+        for (rel_path, abs_path1) in &files1 {
+            if !files2.contains_key(rel_path) {
+                self.progress.begin_file(rel_path);
+                if self.keeps("Removed", rel_path) {
+                    if let Some(value) = self.parse_standalone(abs_path1)? {
+                        sink(vec![DiffResult::Removed(rel_path.clone(), value).into()])?;
+                    }
+                }
+                self.progress.end_file(file_size(abs_path1));
+            }
+        }
+
+        // Files that only exist in the new directory
+        for (rel_path, abs_path2) in &files2 {
+            if !files1.contains_key(rel_path) {
+                self.progress.begin_file(rel_path);
+                if self.keeps("Added", rel_path) {
+                    if let Some(value) = self.parse_standalone(abs_path2)? {
+                        sink(vec![DiffResult::Added(rel_path.clone(), value).into()])?;
+                    }
+                }
+                self.progress.end_file(file_size(abs_path2));
+            }
+        }
+
+        // Files that exist in both directories
+        for (rel_path, abs_path1) in &files1 {
+            if let Some(abs_path2) = files2.get(rel_path) {
+                self.progress.begin_file(rel_path);
+                if self.excludes(rel_path) {
+                    self.progress
+                        .end_file(file_size(abs_path1) + file_size(abs_path2));
+                    continue;
+                }
+                match self.diff_files(abs_path1, abs_path2, Some(rel_path)) {
+                    Ok(mut file_results) => {
+                        for result in &mut file_results {
+                            let path = result_path_mut(&mut result.result);
+                            *path = format!("{rel_path}/{path}");
+                        }
+                        sink(file_results)?;
+                    }
+                    Err(e) if e.is::<Cancelled>() => return Err(e),
+                    // Like the core, files that fail to compare are skipped
+                    Err(_) => {}
+                }
+                self.progress
+                    .end_file(file_size(abs_path1) + file_size(abs_path2));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Parse a model file. Safetensors tensors whose results would all be
+    /// filtered out are not read when `tensors`, the path the tensors of the
+    /// file end up under, is given.
+    fn parse_file(
+        &mut self,
+        path: &Path,
+        format: FileFormat,
+        tensors: Option<&str>,
+    ) -> Result<Model> {
+        self.cancel.check()?;
+        let options = self.options;
+        let extended = options.is_some_and(|o| o.extended_stats);
+
+        let result = match format {
+            FileFormat::Safetensors => {
+                let reader =
+                    BufReader::new(File::open(path).map_err(|e| DiffaiError::io(&e, path))?);
+                let cancel = self.cancel;
+                let progress = &mut self.progress;
+                parse_safetensors(reader, extended, &mut |name, bytes| {
+                    cancel.check()?;
+                    progress.tensor(name, bytes);
+                    Ok(!tensors
+                        .is_some_and(|tensors| excludes(options, &format!("{tensors}.{name}"))))
+                })
+            }
+            _ => parse_file_by_format(path, format).map(Model::from),
+        };
+
+        result.map_err(|e| {
+            if e.is::<Cancelled>() || e.is::<DiffaiError>() {
+                e
+            } else {
+                DiffaiError::parse(e, path).into()
+            }
+        })
+    }
+
+    fn excludes(&self, path: &str) -> bool {
+        excludes(self.options, path)
+    }
+
+    /// Whether a `diff_type` result for the file at `path` is kept, so the
+    /// file needs to be read.
+    fn keeps(&self, diff_type: &str, path: &str) -> bool {
+        !self.excludes(path) && self.options.is_none_or(|o| o.types.accepts(diff_type))
+    }
+
+    /// Parse a file that has no counterpart, returning `None` when it cannot be read.
+    fn parse_standalone(&mut self, path: &Path) -> Result<Option<Value>> {
+        let format = detect_format(path)?;
+
+        match self.parse_file(path, format, None) {
+            Ok(model) => Ok(Some(model.value)),
+            Err(e) if e.is::<Cancelled>() => Err(e),
+            Err(_) => Ok(None),
+        }
+    }
+}
+
+/// Whether the options drop every result at or below `path`.
+fn excludes(options: Option<&CompareOptions>, path: &str) -> bool {
+    options.is_some_and(|o| o.filter.excludes_all_below(path))
+}
+
+fn detect_format(path: &Path) -> Result<FileFormat> {
+    detect_format_from_path(path).map_err(|e| {
+        DiffaiError::new(ErrorCode::UnsupportedFormat, e.to_string())
+            .with_path(path)
+            .into()
+    })
+}
+
+/// Collect the files under `dir` that the core knows how to parse, keyed by
+/// their path relative to `dir`.
+fn model_files(dir: &Path) -> Result<BTreeMap<String, PathBuf>> {
+    let mut files = Vec::new();
+    collect_files(dir, &mut files)?;
+
+    Ok(files
+        .into_iter()
+        .filter(|path| detect_format_from_path(path).is_ok())
+        .filter_map(|path| {
+            let rel = path.strip_prefix(dir).ok()?.to_string_lossy().to_string();
+            Some((rel, path))
+        })
+        .collect())
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    let entries = fs::read_dir(dir).map_err(|e| DiffaiError::io(&e, dir))?;
+    for entry in entries {
+        let path = entry.map_err(|e| DiffaiError::io(&e, dir))?.path();
+
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else if path.is_file() {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+fn file_size(path: &Path) -> u64 {
+    fs::metadata(path).map(|m| m.len()).unwrap_or(0)
+}
+
+pub(crate) fn result_path_mut(result: &mut DiffResult) -> &mut String {
+    match result {
+        DiffResult::Added(path, _)
+        | DiffResult::Removed(path, _)
+        | DiffResult::Modified(path, _, _)
+        | DiffResult::TypeChanged(path, _, _)
+        | DiffResult::TensorShapeChanged(path, _, _)
+        | DiffResult::TensorStatsChanged(path, _, _)
+        | DiffResult::TensorDataChanged(path, _, _)
+        | DiffResult::ModelArchitectureChanged(path, _, _)
+        | DiffResult::WeightSignificantChange(path, _)
+        | DiffResult::ActivationFunctionChanged(path, _, _)
+        | DiffResult::LearningRateChanged(path, _, _)
+        | DiffResult::OptimizerChanged(path, _, _)
+        | DiffResult::LossChange(path, _, _)
+        | DiffResult::AccuracyChange(path, _, _)
+        | DiffResult::ModelVersionChanged(path, _, _) => path,
+    }
+}
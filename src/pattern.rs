@@ -0,0 +1,188 @@
+use napi::bindgen_prelude::*;
+use napi::{JsObject, JsUnknown, ValueType};
+use regex::Regex;
+
+use crate::error::{DiffaiError, DiffaiResult, ErrorCode};
+use crate::keypath;
+
+/// A path pattern as passed from JS: a glob string or a `RegExp`.
+pub enum JsPathPattern {
+    Glob(String),
+    Regex { source: String, flags: String },
+}
+
+impl FromNapiValue for JsPathPattern {
+    unsafe fn from_napi_value(env: sys::napi_env, napi_val: sys::napi_value) -> Result<Self> {
+        let value = JsUnknown::from_napi_value(env, napi_val)?;
+        match value.get_type()? {
+            ValueType::String => Ok(Self::Glob(String::from_napi_value(env, napi_val)?)),
+            ValueType::Object => {
+                let object = JsObject::from_napi_value(env, napi_val)?;
+                match object.get::<_, String>("source")? {
+                    Some(source) => Ok(Self::Regex {
+                        source,
+                        flags: object.get::<_, String>("flags")?.unwrap_or_default(),
+                    }),
+                    None => Err(Error::new(
+                        Status::InvalidArg,
+                        "Expected a glob string or a RegExp".to_string(),
+                    )),
+                }
+            }
+            other => Err(Error::new(
+                Status::InvalidArg,
+                format!("Expected a glob string or a RegExp, got {other}"),
+            )),
+        }
+    }
+}
+
+/// A compiled path pattern.
+///
+/// Globs match whole paths: `*` matches within one key (including its array
+/// indices), `?` matches one such character and `**` matches anything, so
+/// `**.bias` matches `bias`, `fc.bias` and `model.safetensors/fc.bias`.
+/// Regular expressions match anywhere in the path unless anchored.
+pub(crate) struct PathPattern {
+    regex: Regex,
+    specificity: usize,
+}
+
+impl PathPattern {
+    pub(crate) fn compile(pattern: JsPathPattern) -> DiffaiResult<Self> {
+        let (source, specificity) = match pattern {
+            JsPathPattern::Glob(glob) => (glob_to_regex(&glob), glob_literals(&glob)),
+            JsPathPattern::Regex { source, flags } => {
+                // JS flags without a Rust counterpart (g, y, d, u, v) do not
+                // change which paths match
+                let inline: String = flags.chars().filter(|f| "ims".contains(*f)).collect();
+                let specificity = regex_literals(&source);
+                match inline.is_empty() {
+                    true => (source, specificity),
+                    false => (format!("(?{inline}){source}"), specificity),
+                }
+            }
+        };
+
+        let regex = Regex::new(&source).map_err(|e| {
+            DiffaiError::new(
+                ErrorCode::InvalidOption,
+                format!("Invalid path pattern: {e}"),
+            )
+            .with_cause(e)
+        })?;
+        Ok(Self { regex, specificity })
+    }
+
+    /// Whether the pattern matches `path` or one of its ancestors.
+    pub(crate) fn matches_within(&self, path: &str) -> bool {
+        ancestors(path)
+            .iter()
+            .any(|ancestor| self.regex.is_match(ancestor))
+    }
+
+    /// Number of literal characters, used to pick the most specific of
+    /// several matching patterns.
+    pub(crate) fn specificity(&self) -> usize {
+        self.specificity
+    }
+}
+
+/// `path` and the paths above it. Results of a directory comparison are
+/// prefixed with `file/`, so the file and its directories are ancestors too.
+fn ancestors(path: &str) -> Vec<String> {
+    let (file, inner) = match path.rfind('/') {
+        Some(end) => (Some(&path[..end]), &path[end + 1..]),
+        None => (None, path),
+    };
+
+    let mut ancestors = Vec::new();
+    if let Some(file) = file {
+        ancestors.extend(
+            file.match_indices('/')
+                .map(|(end, _)| file[..end].to_string()),
+        );
+        ancestors.push(file.to_string());
+    }
+    let segments = keypath::split(inner);
+    for len in 0..=segments.len() {
+        let inner = keypath::join(&segments[..len]);
+        match file {
+            Some(_) if len == 0 => {}
+            Some(file) => ancestors.push(format!("{file}/{inner}")),
+            None => ancestors.push(inner),
+        }
+    }
+    ancestors
+}
+
+fn glob_to_regex(glob: &str) -> String {
+    let mut regex = String::from("^");
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                // `**.` and `**/` also match no segments at all
+                match chars.peek() {
+                    Some(&separator @ ('.' | '/')) => {
+                        chars.next();
+                        let separator = regex::escape(&separator.to_string());
+                        regex.push_str(&format!("(?:.*{separator})?"));
+                    }
+                    _ => regex.push_str(".*"),
+                }
+            }
+            '*' => regex.push_str("[^./]*"),
+            '?' => regex.push_str("[^./]"),
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex.push('$');
+    regex
+}
+
+fn glob_literals(glob: &str) -> usize {
+    glob.chars().filter(|c| !matches!(c, '*' | '?')).count()
+}
+
+/// Literal characters of a regular expression, skipping metacharacters,
+/// character classes, quantifier bounds and group prefixes like `(?:`.
+fn regex_literals(source: &str) -> usize {
+    let mut count = 0;
+    let mut chars = source.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                if chars
+                    .next()
+                    .is_some_and(|escaped| !escaped.is_alphanumeric())
+                {
+                    count += 1;
+                }
+            }
+            '[' => {
+                let mut escaped = false;
+                for c in chars.by_ref() {
+                    match c {
+                        _ if escaped => escaped = false,
+                        '\\' => escaped = true,
+                        ']' => break,
+                        _ => {}
+                    }
+                }
+            }
+            '{' => {
+                chars.by_ref().find(|&c| c == '}');
+            }
+            '(' => {
+                if chars.clone().next() == Some('?') {
+                    chars.by_ref().find(|&c| matches!(c, ':' | ')' | '>'));
+                }
+            }
+            '.' | '^' | '$' | '*' | '+' | '?' | ')' | '|' => {}
+            _ => count += 1,
+        }
+    }
+    count
+}
@@ -0,0 +1,136 @@
+use std::time::{Duration, Instant};
+
+use napi::bindgen_prelude::*;
+use napi::threadsafe_function::{
+    ErrorStrategy, ThreadSafeCallContext, ThreadsafeFunction, ThreadsafeFunctionCallMode,
+};
+use napi::{JsFunction, NapiRaw};
+use napi_derive::napi;
+
+/// Minimum time between two progress callbacks, so the event loop isn't flooded.
+const REPORT_INTERVAL: Duration = Duration::from_millis(100);
+
+pub(crate) type ProgressCallback = ThreadsafeFunction<JsDiffProgress, ErrorStrategy::Fatal>;
+
+/// An `onProgress` function kept across calls, e.g. by a `Differ`.
+///
+/// Unlike a `ProgressCallback`, holding it does not keep the event loop alive.
+pub(crate) type ProgressCallbackRef = FunctionRef<JsDiffProgress, ()>;
+
+/// Create a `ProgressCallback` for a single comparison from a stored callback.
+pub(crate) fn threadsafe_callback(
+    env: &Env,
+    callback: &ProgressCallbackRef,
+) -> Result<ProgressCallback> {
+    let function = callback.borrow_back(env)?;
+    let function = unsafe { JsFunction::from_napi_value(env.raw(), function.raw()) }?;
+
+    function.create_threadsafe_function(0, |ctx: ThreadSafeCallContext<JsDiffProgress>| {
+        Ok(vec![ctx.value])
+    })
+}
+
+#[napi(object)]
+pub struct JsDiffProgress {
+    /// Number of files compared so far
+    pub files_visited: u32,
+
+    /// Number of model files that will be compared
+    pub files_total: u32,
+
+    /// Bytes read so far
+    pub bytes_read: f64,
+
+    /// Total bytes of all model files that will be read
+    pub bytes_total: f64,
+
+    /// File currently being compared (relative to the compared directories)
+    pub current_file: Option<String>,
+
+    /// Tensor currently being read
+    pub current_tensor: Option<String>,
+}
+
+/// Tracks walker progress and forwards throttled snapshots to `onProgress`.
+#[derive(Default)]
+pub(crate) struct ProgressReporter {
+    callback: Option<ProgressCallback>,
+    files_visited: u32,
+    files_total: u32,
+    bytes_read: u64,
+    bytes_total: u64,
+    entry_start: u64,
+    current_file: Option<String>,
+    current_tensor: Option<String>,
+    last_report: Option<Instant>,
+}
+
+impl ProgressReporter {
+    pub(crate) fn new(callback: Option<ProgressCallback>) -> Self {
+        Self {
+            callback,
+            ..Self::default()
+        }
+    }
+
+    pub(crate) fn set_totals(&mut self, files_total: usize, bytes_total: u64) {
+        self.files_total = files_total as u32;
+        self.bytes_total = bytes_total;
+        self.report(false);
+    }
+
+    /// Start comparing one file (or a file that only exists on one side).
+    pub(crate) fn begin_file(&mut self, name: &str) {
+        self.entry_start = self.bytes_read;
+        self.current_file = Some(name.to_string());
+        self.current_tensor = None;
+        self.report(false);
+    }
+
+    pub(crate) fn tensor(&mut self, name: &str, bytes: u64) {
+        self.bytes_read += bytes;
+        self.current_tensor = Some(name.to_string());
+        self.report(false);
+    }
+
+    /// Finish the current file; `bytes` is the size of everything read for it.
+    pub(crate) fn end_file(&mut self, bytes: u64) {
+        self.bytes_read = self.entry_start + bytes;
+        self.files_visited += 1;
+        self.current_tensor = None;
+        self.report(false);
+    }
+
+    /// Always deliver the final state, regardless of throttling.
+    pub(crate) fn finish(&mut self) {
+        self.report(true);
+    }
+
+    fn report(&mut self, force: bool) {
+        let Some(callback) = &self.callback else {
+            return;
+        };
+
+        let now = Instant::now();
+        if !force
+            && self
+                .last_report
+                .is_some_and(|last| now.duration_since(last) < REPORT_INTERVAL)
+        {
+            return;
+        }
+        self.last_report = Some(now);
+
+        callback.call(
+            JsDiffProgress {
+                files_visited: self.files_visited,
+                files_total: self.files_total,
+                bytes_read: self.bytes_read as f64,
+                bytes_total: self.bytes_total as f64,
+                current_file: self.current_file.clone(),
+                current_tensor: self.current_tensor.clone(),
+            },
+            ThreadsafeFunctionCallMode::NonBlocking,
+        );
+    }
+}
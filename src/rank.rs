@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+
+use diffai_core::{DiffResult, TensorStats};
+use napi_derive::napi;
+
+use crate::entry::DiffEntry;
+use crate::error::{DiffaiError, DiffaiResult, ErrorCode};
+use crate::JsDiffResult;
+
+/// One tensor of the ranking returned by `rankChanges`
+#[napi(object)]
+pub struct JsRankedChange {
+    /// Path of the tensor
+    pub path: String,
+
+    /// Value of the ranking metric
+    pub score: f64,
+
+    /// Result the score was computed from
+    #[napi(ts_type = "DiffResult")]
+    pub result: JsDiffResult,
+}
+
+/// How much a tensor changed, as chosen by the `by` option.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) enum Metric {
+    /// `|new mean - old mean|`
+    #[default]
+    MeanShift,
+    /// Factor by which the standard deviation grew or shrank, at least 1
+    StdRatio,
+    /// Magnitude of a `WeightSignificantChange`
+    ChangeMagnitude,
+    /// Change of the L2 norm relative to the old norm. Without extended
+    /// statistics the norms are estimated from the mean, standard deviation
+    /// and element count
+    RelativeL2,
+    /// Change of the largest absolute value
+    MaxAbs,
+}
+
+impl Metric {
+    pub(crate) fn parse(name: &str) -> DiffaiResult<Self> {
+        match name {
+            "meanShift" => Ok(Self::MeanShift),
+            "stdRatio" => Ok(Self::StdRatio),
+            "changeMagnitude" => Ok(Self::ChangeMagnitude),
+            "relativeL2" => Ok(Self::RelativeL2),
+            "maxAbs" => Ok(Self::MaxAbs),
+            _ => Err(DiffaiError::new(
+                ErrorCode::InvalidOption,
+                format!(
+                    "Invalid ranking metric: '{name}'. Expected one of: meanShift, stdRatio, changeMagnitude, relativeL2, maxAbs"
+                ),
+            )),
+        }
+    }
+
+    /// Score of `entry`, or `None` if the metric cannot be computed from it.
+    fn score(self, entry: &DiffEntry) -> Option<f64> {
+        let score = match (self, &entry.result) {
+            (Self::MeanShift, DiffResult::TensorStatsChanged(_, old, new)) => {
+                (new.mean - old.mean).abs()
+            }
+            (Self::MeanShift, DiffResult::TensorDataChanged(_, old_mean, new_mean)) => {
+                (new_mean - old_mean).abs()
+            }
+            (Self::StdRatio, DiffResult::TensorStatsChanged(_, old, new)) => {
+                match (old.std, new.std) {
+                    (old, new) if old == new => 1.0,
+                    (old, new) if old > new => old / new,
+                    (old, new) => new / old,
+                }
+            }
+            (Self::ChangeMagnitude, DiffResult::WeightSignificantChange(_, magnitude)) => {
+                *magnitude
+            }
+            (Self::RelativeL2, DiffResult::TensorStatsChanged(_, old, new)) => {
+                let (old, new) = match entry.extended() {
+                    (Some(old_extended), Some(new_extended)) => {
+                        old_extended.l2_norm.zip(new_extended.l2_norm)
+                    }
+                    _ => None,
+                }
+                .unwrap_or_else(|| (l2_norm(old), l2_norm(new)));
+                match old == new {
+                    true => 0.0,
+                    false => (new - old).abs() / old,
+                }
+            }
+            (Self::MaxAbs, DiffResult::TensorStatsChanged(_, old, new)) => {
+                (max_abs(new) - max_abs(old)).abs()
+            }
+            _ => return None,
+        };
+        (!score.is_nan()).then_some(score)
+    }
+}
+
+/// L2 norm of a tensor with these statistics, `sqrt(n * (mean² + std²))`,
+/// for results without the actual norm.
+fn l2_norm(stats: &TensorStats) -> f64 {
+    (stats.element_count as f64 * (stats.mean * stats.mean + stats.std * stats.std)).sqrt()
+}
+
+fn max_abs(stats: &TensorStats) -> f64 {
+    stats.min.abs().max(stats.max.abs())
+}
+
+/// The `k` tensors with the highest score, highest first.
+///
+/// A tensor with several results is ranked by its highest score. Ties are
+/// broken by path, so the ranking does not depend on the order of `results`.
+pub(crate) fn rank(results: Vec<DiffEntry>, metric: Metric, k: usize) -> Vec<(DiffEntry, f64)> {
+    let mut best: HashMap<String, (DiffEntry, f64)> = HashMap::new();
+    for entry in results {
+        let Some(score) = metric.score(&entry) else {
+            continue;
+        };
+        match best.get(entry.path()) {
+            Some((_, best_score)) if *best_score >= score => {}
+            _ => {
+                best.insert(entry.path().to_string(), (entry, score));
+            }
+        }
+    }
+
+    let mut ranked: Vec<_> = best.into_iter().collect();
+    ranked.sort_by(|(a_path, (_, a)), (b_path, (_, b))| {
+        b.total_cmp(a).then_with(|| a_path.cmp(b_path))
+    });
+    ranked
+        .into_iter()
+        .take(k)
+        .map(|(_, ranked)| ranked)
+        .collect()
+}
@@ -0,0 +1,259 @@
+use std::collections::BTreeMap;
+use std::io::{Read, Seek, SeekFrom};
+
+use anyhow::{anyhow, Result};
+use serde_json::{json, Value};
+
+use crate::tensors::{ExtendedStats, Model};
+
+/// Same limit as the `safetensors` crate uses to reject corrupt headers.
+const MAX_HEADER_SIZE: u64 = 100_000_000;
+
+struct TensorEntry {
+    name: String,
+    dtype: String,
+    shape: Vec<usize>,
+    start: u64,
+    end: u64,
+}
+
+/// Parse a safetensors stream into the same JSON layout `diffai_core` produces.
+///
+/// The NaN and infinity counts of each tensor are kept next to the value. With
+/// `extended`, every `ExtendedStats` is computed and also added to the
+/// `data_summary` of the tensor.
+///
+/// Unlike the core parser this reads one tensor at a time, so the whole file is
+/// never held in memory. `on_tensor` is called with the name and byte size of
+/// each tensor before it is read and returns whether to read it; tensors that
+/// are not read are left out of the value. Returning an error stops parsing.
+pub(crate) fn parse_safetensors<R: Read + Seek>(
+    mut reader: R,
+    extended: bool,
+    on_tensor: &mut dyn FnMut(&str, u64) -> Result<bool>,
+) -> Result<Model> {
+    let (data_start, entries) = read_header(&mut reader)?;
+    let mut tensors = serde_json::Map::new();
+    let mut tensor_stats = BTreeMap::new();
+
+    for entry in entries {
+        if !on_tensor(&entry.name, entry.end - entry.start)? {
+            continue;
+        }
+
+        let mut tensor_info = serde_json::Map::new();
+        tensor_info.insert("shape".to_string(), json!(entry.shape));
+        tensor_info.insert("dtype".to_string(), Value::String(entry.dtype.clone()));
+
+        if has_stats(&entry.dtype) && entry.end > entry.start {
+            let mut data = vec![0u8; (entry.end - entry.start) as usize];
+            reader.seek(SeekFrom::Start(data_start + entry.start))?;
+            reader
+                .read_exact(&mut data)
+                .map_err(|e| anyhow!("Failed to read data for tensor '{}': {e}", entry.name))?;
+
+            let values = decode_values(&entry.dtype, &data);
+            if let Some((summary, stats)) = compute_tensor_stats(values, extended) {
+                tensor_info.insert("data_summary".to_string(), summary);
+                tensor_stats.insert(entry.name.clone(), stats);
+            }
+        }
+
+        tensors.insert(entry.name, Value::Object(tensor_info));
+    }
+
+    let mut result = serde_json::Map::new();
+    result.insert(
+        "model_type".to_string(),
+        Value::String("safetensors".to_string()),
+    );
+    result.insert("tensors".to_string(), Value::Object(tensors));
+
+    Ok(Model {
+        value: Value::Object(result),
+        tensors: tensor_stats,
+    })
+}
+
+/// Read the header and return the offset of the data section with the tensor
+/// entries sorted by their position in it.
+fn read_header<R: Read + Seek>(reader: &mut R) -> Result<(u64, Vec<TensorEntry>)> {
+    let mut len_bytes = [0u8; 8];
+    reader
+        .read_exact(&mut len_bytes)
+        .map_err(|_| anyhow!("Invalid safetensors header: file is too short"))?;
+    let header_len = u64::from_le_bytes(len_bytes);
+    if header_len > MAX_HEADER_SIZE {
+        return Err(anyhow!(
+            "Invalid safetensors header: header size {header_len} is too large"
+        ));
+    }
+
+    let mut header_bytes = vec![0u8; header_len as usize];
+    reader
+        .read_exact(&mut header_bytes)
+        .map_err(|_| anyhow!("Invalid safetensors header: header is truncated"))?;
+    let header: serde_json::Map<String, Value> = serde_json::from_slice(&header_bytes)
+        .map_err(|e| anyhow!("Invalid safetensors header: {e}"))?;
+
+    let mut entries = Vec::with_capacity(header.len());
+    for (name, info) in header {
+        if name == "__metadata__" {
+            continue;
+        }
+
+        let dtype = info
+            .get("dtype")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow!("Invalid safetensors header: tensor '{name}' has no dtype"))?
+            .to_string();
+        let shape = info
+            .get("shape")
+            .and_then(|v| v.as_array())
+            .and_then(|dims| {
+                dims.iter()
+                    .map(|d| d.as_u64().map(|d| d as usize))
+                    .collect::<Option<Vec<_>>>()
+            })
+            .ok_or_else(|| anyhow!("Invalid safetensors header: tensor '{name}' has no shape"))?;
+        let (start, end) = match info
+            .get("data_offsets")
+            .and_then(|v| v.as_array())
+            .map(|o| o.iter().filter_map(|v| v.as_u64()).collect::<Vec<_>>())
+            .as_deref()
+        {
+            Some([start, end]) if start <= end => (*start, *end),
+            _ => {
+                return Err(anyhow!(
+                    "Invalid safetensors header: tensor '{name}' has invalid data_offsets"
+                ))
+            }
+        };
+
+        let size = dtype_size(&dtype).ok_or_else(|| {
+            anyhow!("Invalid safetensors header: tensor '{name}' has unknown dtype '{dtype}'")
+        })?;
+        // The shape is untrusted, so its byte length may not fit in a u64
+        let expected = shape
+            .iter()
+            .try_fold(size, |bytes, &dim| bytes.checked_mul(dim as u64))
+            .ok_or_else(|| {
+                anyhow!("Invalid safetensors header: tensor '{name}' has a shape of more than 2^64 bytes")
+            })?;
+        if end - start != expected {
+            return Err(anyhow!(
+                "Invalid safetensors header: tensor '{name}' spans {} bytes, expected {expected}",
+                end - start
+            ));
+        }
+
+        entries.push(TensorEntry {
+            name,
+            dtype,
+            shape,
+            start,
+            end,
+        });
+    }
+
+    entries.sort_by_key(|entry| entry.start);
+
+    let data_start = 8 + header_len;
+    let data_len = entries.last().map_or(0, |entry| entry.end);
+    let stream_len = reader.seek(SeekFrom::End(0))?;
+    if data_start.checked_add(data_len) != Some(stream_len) {
+        return Err(anyhow!(
+            "Invalid safetensors header: expected {} bytes of tensor data, found {}",
+            data_len,
+            stream_len.saturating_sub(data_start)
+        ));
+    }
+
+    Ok((data_start, entries))
+}
+
+fn dtype_size(dtype: &str) -> Option<u64> {
+    match dtype {
+        "BOOL" | "U8" | "I8" | "F8_E5M2" | "F8_E4M3" => Some(1),
+        "I16" | "U16" | "F16" | "BF16" => Some(2),
+        "I32" | "U32" | "F32" => Some(4),
+        "I64" | "U64" | "F64" => Some(8),
+        _ => None,
+    }
+}
+
+/// Whether `diffai_core` computes a `data_summary` for this dtype.
+fn has_stats(dtype: &str) -> bool {
+    matches!(
+        dtype,
+        "F32" | "F64" | "I8" | "I16" | "I32" | "I64" | "U8" | "U16" | "U32" | "U64"
+    )
+}
+
+fn decode_values(dtype: &str, data: &[u8]) -> Vec<f64> {
+    let chunks = data.chunks_exact(dtype_size(dtype).unwrap_or(1) as usize);
+    match dtype {
+        "F32" => chunks
+            .map(|c| f32::from_le_bytes(c.try_into().unwrap()) as f64)
+            .collect(),
+        "F64" => chunks
+            .map(|c| f64::from_le_bytes(c.try_into().unwrap()))
+            .collect(),
+        "I32" => chunks
+            .map(|c| i32::from_le_bytes(c.try_into().unwrap()) as f64)
+            .collect(),
+        "I64" => chunks
+            .map(|c| i64::from_le_bytes(c.try_into().unwrap()) as f64)
+            .collect(),
+        "I16" => chunks
+            .map(|c| i16::from_le_bytes(c.try_into().unwrap()) as f64)
+            .collect(),
+        "I8" => data.iter().map(|&x| x as i8 as f64).collect(),
+        "U8" => data.iter().map(|&x| x as f64).collect(),
+        "U16" => chunks
+            .map(|c| u16::from_le_bytes(c.try_into().unwrap()) as f64)
+            .collect(),
+        "U32" => chunks
+            .map(|c| u32::from_le_bytes(c.try_into().unwrap()) as f64)
+            .collect(),
+        "U64" => chunks
+            .map(|c| u64::from_le_bytes(c.try_into().unwrap()) as f64)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// The `data_summary` of a tensor and its `ExtendedStats`, which are only the
+/// NaN and infinity counts unless `extended`. Then they are added to the
+/// `data_summary` too, except for the histogram.
+fn compute_tensor_stats(values: Vec<f64>, extended: bool) -> Option<(Value, ExtendedStats)> {
+    if values.is_empty() {
+        return None;
+    }
+
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / n;
+    let variance = values.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n;
+    let std = variance.sqrt();
+    let min = values.iter().cloned().fold(f64::INFINITY, f64::min);
+    let max = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    let mut summary = json!({
+        "mean": mean,
+        "std": std,
+        "min": min,
+        "max": max
+    });
+
+    if !extended {
+        return Some((summary, ExtendedStats::counts(values.into_iter())));
+    }
+    let stats = ExtendedStats::new(values);
+    if let (Value::Object(summary), Value::Object(mut extended)) =
+        (&mut summary, serde_json::to_value(&stats).ok()?)
+    {
+        // Bin counts are for reports to draw, not to be diffed one by one
+        extended.remove("histogram");
+        summary.extend(extended);
+    }
+    Some((summary, stats))
+}
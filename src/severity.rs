@@ -0,0 +1,93 @@
+use std::collections::HashMap;
+
+use napi_derive::napi;
+
+use crate::error::{DiffaiError, DiffaiResult, ErrorCode};
+
+/// How much a difference matters, from least to most
+#[napi(string_enum)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// Expected to change between checkpoints, like values and losses
+    #[napi(value = "info")]
+    Info,
+    /// Worth a look, like added keys or shifted tensor statistics
+    #[napi(value = "warning")]
+    Warning,
+    /// Breaks compatibility or training, like tensor shapes, the architecture
+    /// or NaN values
+    #[napi(value = "critical")]
+    Critical,
+}
+
+/// Every `diffType` with its default severity.
+const DEFAULT_SEVERITIES: [(&str, Severity); 17] = [
+    ("Added", Severity::Warning),
+    ("Removed", Severity::Warning),
+    ("Modified", Severity::Info),
+    ("TypeChanged", Severity::Warning),
+    ("TensorShapeChanged", Severity::Critical),
+    ("TensorStatsChanged", Severity::Warning),
+    ("TensorDataChanged", Severity::Info),
+    ("ModelArchitectureChanged", Severity::Critical),
+    ("WeightSignificantChange", Severity::Warning),
+    ("ActivationFunctionChanged", Severity::Critical),
+    ("LearningRateChanged", Severity::Warning),
+    ("OptimizerChanged", Severity::Warning),
+    ("LossChange", Severity::Info),
+    ("AccuracyChange", Severity::Info),
+    ("ModelVersionChanged", Severity::Warning),
+    ("TensorNanIntroduced", Severity::Critical),
+    ("TensorInfIntroduced", Severity::Critical),
+];
+
+/// Severity of each `diffType`, with the overrides passed as `severities`.
+#[derive(Default)]
+pub(crate) struct Severities {
+    overrides: HashMap<&'static str, Severity>,
+}
+
+impl Severities {
+    pub(crate) fn new(overrides: HashMap<String, String>) -> DiffaiResult<Self> {
+        let overrides = overrides
+            .iter()
+            .map(|(diff_type, severity)| Ok((parse_diff_type(diff_type)?, parse(severity)?)))
+            .collect::<DiffaiResult<_>>()?;
+        Ok(Self { overrides })
+    }
+
+    pub(crate) fn of(&self, diff_type: &str) -> Severity {
+        self.overrides.get(diff_type).copied().unwrap_or_else(|| {
+            DEFAULT_SEVERITIES
+                .iter()
+                .find(|(name, _)| *name == diff_type)
+                .map_or(Severity::Info, |&(_, severity)| severity)
+        })
+    }
+}
+
+pub(crate) fn parse(name: &str) -> DiffaiResult<Severity> {
+    match name {
+        "info" => Ok(Severity::Info),
+        "warning" => Ok(Severity::Warning),
+        "critical" => Ok(Severity::Critical),
+        _ => Err(DiffaiError::new(
+            ErrorCode::InvalidOption,
+            format!("Invalid severity: '{name}'. Expected one of: info, warning, critical"),
+        )),
+    }
+}
+
+/// The `diffType` called `name`, as the static string results use.
+pub(crate) fn parse_diff_type(name: &str) -> DiffaiResult<&'static str> {
+    DEFAULT_SEVERITIES
+        .iter()
+        .map(|&(diff_type, _)| diff_type)
+        .find(|diff_type| *diff_type == name)
+        .ok_or_else(|| {
+            DiffaiError::new(
+                ErrorCode::InvalidOption,
+                format!("Unknown diff type: '{name}'"),
+            )
+        })
+}
@@ -0,0 +1,273 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+
+use napi::bindgen_prelude::*;
+use napi::{JsDeferred, JsFunction, JsObject, JsUnknown};
+use napi_derive::napi;
+
+use crate::cancel::{AbortListener, CancelToken, Cancelled};
+use crate::entry::DiffEntry;
+use crate::error::{DiffaiError, DiffaiResult, IntoJsResult};
+use crate::{convert_diff_result, JsDiffResult};
+
+/// Number of results per batch when `batchSize` is not given.
+pub(crate) const DEFAULT_BATCH_SIZE: usize = 1000;
+
+/// Batches produced ahead of the consumer; keeps memory bounded when the
+/// iterator is read slower than results are produced.
+const QUEUE_DEPTH: usize = 2;
+
+type Batch = DiffaiResult<Vec<DiffEntry>>;
+type Resolver = Box<dyn FnOnce(Env) -> Result<JsDiffStreamResult> + Send>;
+type Deferred = JsDeferred<JsDiffStreamResult, Resolver>;
+
+/// One step of a `DiffStream`, shaped like an `IteratorResult`
+#[napi(object)]
+pub struct JsDiffStreamResult {
+    pub done: bool,
+    pub value: Option<Vec<JsDiffResult>>,
+}
+
+/// Batches on their way from the producing thread to the `DiffStream`.
+///
+/// Promises of `next()` calls made before a batch is ready wait here and are
+/// settled by the producer, so no threadpool worker is held while waiting.
+#[derive(Default)]
+struct Channel {
+    state: Mutex<State>,
+    /// Signalled when the queue has room again or the stream was closed
+    room: Condvar,
+}
+
+#[derive(Default)]
+struct State {
+    /// Batches produced before they were asked for
+    queue: VecDeque<Batch>,
+    /// Pending `next()` calls, settled in order
+    waiting: VecDeque<Deferred>,
+    /// Set once the producer finished or the stream was closed
+    done: bool,
+    /// Listener of the `AbortSignal` passed in, removed once the stream is done
+    abort_listener: Option<AbortListener>,
+}
+
+/// Async iterator over batches of difference objects
+///
+/// Returned by `diffStream` and `diffPathsStream`; use it with `for await`.
+#[napi]
+pub struct DiffStream {
+    channel: Arc<Channel>,
+    cancel: CancelToken,
+}
+
+#[napi]
+impl DiffStream {
+    /// Resolve with the next batch, or `{ done: true }` once all results were read
+    #[napi(ts_return_type = "Promise<IteratorResult<Array<DiffResult>, undefined>>")]
+    pub fn next(&self, env: Env) -> Result<JsObject> {
+        let (deferred, promise) = env.create_deferred()?;
+
+        let mut state = self.channel.state.lock().unwrap();
+        if let Some(batch) = state.queue.pop_front() {
+            drop(state);
+            self.channel.room.notify_one();
+            deferred.resolve(settle(batch));
+        } else if state.done {
+            let abort_listener = state.abort_listener.take();
+            drop(state);
+            deferred.resolve(finished(abort_listener));
+        } else {
+            state.waiting.push_back(deferred);
+        }
+
+        Ok(promise)
+    }
+
+    /// Stop producing results, e.g. when a `for await` loop exits early
+    #[napi(
+        js_name = "return",
+        ts_return_type = "Promise<IteratorResult<Array<DiffResult>, undefined>>"
+    )]
+    pub fn close(&self, env: Env) -> Result<JsObject> {
+        self.cancel.cancel();
+
+        let (waiting, abort_listener) = {
+            let mut state = self.channel.state.lock().unwrap();
+            state.done = true;
+            state.queue.clear();
+            (
+                std::mem::take(&mut state.waiting),
+                state.abort_listener.take(),
+            )
+        };
+        // Wakes a producer waiting for room, which then sees the stream closed
+        self.channel.room.notify_all();
+        for deferred in waiting {
+            deferred.resolve(finished(None));
+        }
+
+        let (deferred, promise) = env.create_deferred()?;
+        deferred.resolve(finished(abort_listener));
+        Ok(promise)
+    }
+}
+
+/// A stream abandoned without `return()`, e.g. one that was garbage-collected,
+/// stops its producer, which would otherwise wait for room forever.
+impl Drop for DiffStream {
+    fn drop(&mut self) {
+        self.cancel.cancel();
+        {
+            let mut state = self.channel.state.lock().unwrap();
+            state.done = true;
+            state.queue.clear();
+        }
+        self.channel.room.notify_all();
+    }
+}
+
+/// Settle a `next()` promise with `{ done: true }`, removing the abort
+/// listener if it is still registered.
+fn finished(abort_listener: Option<AbortListener>) -> Resolver {
+    Box::new(move |env| {
+        if let Some(listener) = abort_listener {
+            listener.remove(env)?;
+        }
+        Ok(JsDiffStreamResult {
+            done: true,
+            value: None,
+        })
+    })
+}
+
+/// Settle a `next()` promise with `batch`.
+fn settle(batch: Batch) -> Resolver {
+    Box::new(move |env| {
+        let value = batch
+            .and_then(|results| results.into_iter().map(convert_diff_result).collect())
+            .into_js(&env)?;
+
+        Ok(JsDiffStreamResult {
+            done: false,
+            value: Some(value),
+        })
+    })
+}
+
+/// Groups results into batches and hands them to the `DiffStream`.
+pub(crate) struct BatchSender {
+    channel: Arc<Channel>,
+    cancel: CancelToken,
+    batch: Vec<DiffEntry>,
+    batch_size: usize,
+}
+
+impl BatchSender {
+    pub(crate) fn push(&mut self, results: Vec<DiffEntry>) -> anyhow::Result<()> {
+        for result in results {
+            self.batch.push(result);
+            if self.batch.len() >= self.batch_size {
+                self.flush()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> anyhow::Result<()> {
+        self.cancel.check()?;
+        if self.batch.is_empty() {
+            return Ok(());
+        }
+
+        let batch = std::mem::replace(&mut self.batch, Vec::with_capacity(self.batch_size));
+        self.send(Ok(batch))
+    }
+
+    /// Hand `batch` to a waiting `next()` call, or queue it, waiting for room
+    /// if the queue is full.
+    fn send(&self, batch: Batch) -> anyhow::Result<()> {
+        let mut state = self.channel.state.lock().unwrap();
+        loop {
+            // The stream was closed or dropped
+            if state.done {
+                return Err(Cancelled.into());
+            }
+            if let Some(deferred) = state.waiting.pop_front() {
+                drop(state);
+                deferred.resolve(settle(batch));
+                return Ok(());
+            }
+            if state.queue.len() < QUEUE_DEPTH {
+                state.queue.push_back(batch);
+                return Ok(());
+            }
+            state = self.channel.room.wait(state).unwrap();
+        }
+    }
+
+    /// Send the remaining results, or the error that ended the comparison,
+    /// and end the stream.
+    fn finish(mut self, outcome: anyhow::Result<()>) {
+        if let Err(e) = outcome.and_then(|_| self.flush()) {
+            // Fails quietly when the stream was already closed
+            let _ = self.send(Err(DiffaiError::from(e)));
+        }
+
+        let mut state = self.channel.state.lock().unwrap();
+        state.done = true;
+        let waiting = std::mem::take(&mut state.waiting);
+        // Otherwise the listener is removed by the `next()` call that sees the
+        // stream done
+        let mut abort_listener = match waiting.is_empty() {
+            true => None,
+            false => state.abort_listener.take(),
+        };
+        drop(state);
+
+        for deferred in waiting {
+            deferred.resolve(finished(abort_listener.take()));
+        }
+    }
+}
+
+/// Run `produce` on its own thread and return a JS async iterator over the
+/// batches it pushes.
+pub(crate) fn spawn<F>(
+    env: &Env,
+    batch_size: usize,
+    cancel: CancelToken,
+    abort_listener: Option<AbortListener>,
+    produce: F,
+) -> Result<JsObject>
+where
+    F: FnOnce(&mut BatchSender) -> anyhow::Result<()> + Send + 'static,
+{
+    let channel = Arc::new(Channel::default());
+    channel.state.lock().unwrap().abort_listener = abort_listener;
+    let mut batches = BatchSender {
+        channel: channel.clone(),
+        cancel: cancel.clone(),
+        batch: Vec::with_capacity(batch_size),
+        batch_size,
+    };
+
+    thread::spawn(move || {
+        let outcome = produce(&mut batches);
+        batches.finish(outcome);
+    });
+
+    let stream = DiffStream { channel, cancel };
+    let mut object = stream.into_instance(*env)?.as_object(*env);
+
+    // `for await` looks the iterator up through `Symbol.asyncIterator`
+    let symbol = env
+        .get_global()?
+        .get_named_property_unchecked::<JsObject>("Symbol")?
+        .get_named_property::<JsUnknown>("asyncIterator")?;
+    let iterator: JsFunction =
+        env.create_function_from_closure("[Symbol.asyncIterator]", |ctx| ctx.this::<JsObject>())?;
+    object.set_property(symbol, iterator)?;
+
+    Ok(object)
+}
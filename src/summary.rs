@@ -0,0 +1,80 @@
+use std::collections::BTreeMap;
+
+use diffai_core::DiffResult;
+use napi_derive::napi;
+
+use crate::entry::DiffEntry;
+use crate::markdown;
+
+/// Aggregate of the results under one path prefix, as returned by `summarize`
+#[napi(object)]
+pub struct JsGroupSummary {
+    /// Path prefix shared by the results, e.g. "model.layers.12" ("" for the
+    /// root)
+    pub prefix: String,
+
+    /// Number of results
+    pub total: u32,
+
+    /// Number of results of each diff type
+    #[napi(ts_type = "Partial<Record<`${DiffType}`, number>>")]
+    pub counts: BTreeMap<String, u32>,
+
+    /// Elements of the tensors whose statistics changed
+    pub parameters: f64,
+
+    /// Mean absolute change of the tensor means (absent without
+    /// `TensorStatsChanged` or `TensorDataChanged` results)
+    pub mean_abs_mean_shift: Option<f64>,
+
+    /// Largest absolute change of a tensor mean (absent without
+    /// `TensorStatsChanged` or `TensorDataChanged` results)
+    pub max_abs_mean_shift: Option<f64>,
+}
+
+/// Summarize results per path prefix, keeping the order in which prefixes
+/// appear. `group_depth` is the number of leading path segments of a prefix;
+/// `None` groups by parent path.
+pub(crate) fn summarize(results: &[DiffEntry], group_depth: Option<usize>) -> Vec<JsGroupSummary> {
+    markdown::group(results, group_depth)
+        .into_iter()
+        .map(|(prefix, members)| summarize_group(prefix, &members))
+        .collect()
+}
+
+fn summarize_group(prefix: String, results: &[&DiffEntry]) -> JsGroupSummary {
+    let mut counts = BTreeMap::new();
+    let mut parameters = 0;
+    let mut shifts = Vec::new();
+
+    for result in results {
+        *counts.entry(result.diff_type().to_string()).or_insert(0) += 1;
+        match &result.result {
+            DiffResult::TensorStatsChanged(_, old, new) => {
+                parameters += new.element_count;
+                shifts.push((new.mean - old.mean).abs());
+            }
+            DiffResult::TensorDataChanged(_, old_mean, new_mean) => {
+                shifts.push((new_mean - old_mean).abs());
+            }
+            _ => {}
+        }
+    }
+
+    let (mean_abs_mean_shift, max_abs_mean_shift) = match shifts.is_empty() {
+        true => (None, None),
+        false => (
+            Some(shifts.iter().sum::<f64>() / shifts.len() as f64),
+            Some(shifts.iter().copied().fold(f64::NEG_INFINITY, f64::max)),
+        ),
+    };
+
+    JsGroupSummary {
+        prefix,
+        total: results.len() as u32,
+        counts,
+        parameters: parameters as f64,
+        mean_abs_mean_shift,
+        max_abs_mean_shift,
+    }
+}
@@ -0,0 +1,357 @@
+use std::collections::BTreeMap;
+
+use diffai_core::{DiffResult, TensorStats};
+use napi::{JsTypedArrayValue, TypedArrayType};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::entry::{DiffEntry, Introduced};
+use crate::error::{DiffaiError, DiffaiResult, ErrorCode};
+use crate::tolerance::Margin;
+
+/// Statistics `TensorStats` does not have, each absent unless computed. Norms
+/// and percentiles are computed over the finite values only.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub(crate) struct ExtendedStats {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) l1_norm: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) l2_norm: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) p1: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) p50: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) p99: Option<f64>,
+    /// Fraction of the elements that are zero
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) zero_fraction: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) nan_count: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) inf_count: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) histogram: Option<Histogram>,
+}
+
+impl ExtendedStats {
+    /// Every statistic. The percentiles are selected in place, so `values` is
+    /// taken rather than copied.
+    pub(crate) fn new(mut values: Vec<f64>) -> Self {
+        let element_count = values.len();
+        let (mut l1_norm, mut sum_of_squares, mut zeros) = (0.0, 0.0, 0);
+        for &value in values.iter().filter(|value| value.is_finite()) {
+            l1_norm += value.abs();
+            sum_of_squares += value * value;
+            zeros += usize::from(value == 0.0);
+        }
+        let counts = Self::counts(values.iter().copied());
+
+        values.retain(|value| value.is_finite());
+        Self {
+            histogram: Histogram::new(&values),
+            l1_norm: Some(l1_norm),
+            l2_norm: Some(f64::sqrt(sum_of_squares)),
+            p1: Some(percentile(&mut values, 0.01)),
+            p50: Some(percentile(&mut values, 0.5)),
+            p99: Some(percentile(&mut values, 0.99)),
+            zero_fraction: Some(match element_count {
+                0 => 0.0,
+                n => zeros as f64 / n as f64,
+            }),
+            ..counts
+        }
+    }
+
+    /// Only the NaN and infinity counts, which need no copy of the values.
+    pub(crate) fn counts(values: impl Iterator<Item = f64>) -> Self {
+        let (mut nan_count, mut inf_count) = (0, 0);
+        for value in values {
+            nan_count += usize::from(value.is_nan());
+            inf_count += usize::from(value.is_infinite());
+        }
+        Self {
+            nan_count: Some(nan_count),
+            inf_count: Some(inf_count),
+            ..Self::default()
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Number of bins of a `Histogram`
+const HISTOGRAM_BINS: usize = 32;
+
+/// Counts of the finite values of a tensor in equal bins from their minimum
+/// `low` to their maximum `high`. A single bin if all the values are equal.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub(crate) struct Histogram {
+    pub(crate) low: f64,
+    pub(crate) high: f64,
+    pub(crate) counts: Vec<usize>,
+}
+
+impl Histogram {
+    /// Of finite values, or `None` if there are none.
+    fn new(values: &[f64]) -> Option<Self> {
+        let low = values.iter().copied().reduce(f64::min)?;
+        let high = values.iter().copied().reduce(f64::max)?;
+        let bins = if high > low { HISTOGRAM_BINS } else { 1 };
+        let mut counts = vec![0; bins];
+        for &value in values {
+            let bin = ((value - low) / (high - low) * bins as f64) as usize;
+            counts[bin.min(bins - 1)] += 1;
+        }
+        Some(Self { low, high, counts })
+    }
+}
+
+/// Percentile `q` of finite values, interpolated linearly like
+/// `numpy.percentile`. NaN if there are no values. Reorders `values`.
+fn percentile(values: &mut [f64], q: f64) -> f64 {
+    if values.is_empty() {
+        return f64::NAN;
+    }
+    let rank = q * (values.len() - 1) as f64;
+    let low = rank.floor() as usize;
+    let (_, &mut low_value, above) = values.select_nth_unstable_by(low, f64::total_cmp);
+    match above.iter().copied().min_by(f64::total_cmp) {
+        Some(high_value) => low_value + (high_value - low_value) * (rank - low as f64),
+        None => low_value,
+    }
+}
+
+/// A parsed model file: the JSON layout `diffai_core` produces, plus the
+/// `ExtendedStats` of the tensors that were read, keyed by name.
+#[derive(Default)]
+pub(crate) struct Model {
+    pub(crate) value: Value,
+    pub(crate) tensors: BTreeMap<String, ExtendedStats>,
+}
+
+impl From<Value> for Model {
+    fn from(value: Value) -> Self {
+        Self {
+            value,
+            tensors: BTreeMap::new(),
+        }
+    }
+}
+
+/// A `TensorNanIntroduced` or `TensorInfIntroduced` result for each tensor
+/// that gained NaN or infinite values from `old` to `new`.
+///
+/// `diffai_core` only sees the `data_summary` of the tensors, whose statistics
+/// turn into nulls rather than telling how many values are not finite.
+pub(crate) fn introduced(old: &Model, new: &Model) -> Vec<DiffEntry> {
+    let mut entries = Vec::new();
+    for (name, new_extended) in &new.tensors {
+        let Some(old_extended) = old.tensors.get(name) else {
+            continue;
+        };
+        if Introduced::between(old_extended, new_extended, None).is_none() {
+            continue;
+        }
+        let (Some(old_stats), Some(new_stats)) = (
+            summary_stats(&old.value, name),
+            summary_stats(&new.value, name),
+        ) else {
+            continue;
+        };
+        entries.push(DiffEntry::tensor_stats(
+            format!("tensors.{name}"),
+            old_stats,
+            new_stats,
+            (old_extended.clone(), new_extended.clone()),
+            None,
+        ));
+    }
+    entries
+}
+
+/// `TensorStats` of a tensor from the shape, dtype and `data_summary` the
+/// safetensors parser wrote, with NaN for the statistics that are null.
+fn summary_stats(model: &Value, name: &str) -> Option<TensorStats> {
+    let tensor = model.get("tensors")?.get(name)?;
+    let shape = tensor
+        .get("shape")?
+        .as_array()?
+        .iter()
+        .map(|d| d.as_u64().map(|d| d as usize))
+        .collect::<Option<Vec<_>>>()?;
+    let summary = tensor.get("data_summary")?;
+    let stat = |key: &str| summary.get(key).and_then(Value::as_f64).unwrap_or(f64::NAN);
+
+    Some(TensorStats {
+        mean: stat("mean"),
+        std: stat("std"),
+        min: stat("min"),
+        max: stat("max"),
+        element_count: shape.iter().product(),
+        shape,
+        dtype: tensor.get("dtype")?.as_str()?.to_string(),
+    })
+}
+
+/// Borrowed view over the memory of a JS TypedArray.
+pub(crate) enum TensorView<'a> {
+    F32(&'a [f32]),
+    F64(&'a [f64]),
+    I8(&'a [i8]),
+    U8(&'a [u8]),
+    I64(&'a [i64]),
+}
+
+impl<'a> TensorView<'a> {
+    pub(crate) fn new(array: &'a JsTypedArrayValue) -> DiffaiResult<Self> {
+        match array.typedarray_type {
+            TypedArrayType::Float32 => Ok(Self::F32(array.as_ref())),
+            TypedArrayType::Float64 => Ok(Self::F64(array.as_ref())),
+            TypedArrayType::Int8 => Ok(Self::I8(array.as_ref())),
+            TypedArrayType::Uint8 => Ok(Self::U8(array.as_ref())),
+            TypedArrayType::BigInt64 => Ok(Self::I64(array.as_ref())),
+            other => Err(DiffaiError::new(
+                ErrorCode::InvalidArgument,
+                format!(
+                    "Unsupported TypedArray type: {other:?}. Expected Float32Array, Float64Array, Int8Array, Uint8Array or BigInt64Array"
+                ),
+            )),
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        match self {
+            Self::F32(data) => data.len(),
+            Self::F64(data) => data.len(),
+            Self::I8(data) => data.len(),
+            Self::U8(data) => data.len(),
+            Self::I64(data) => data.len(),
+        }
+    }
+
+    pub(crate) fn dtype(&self) -> &'static str {
+        match self {
+            Self::F32(_) => "float32",
+            Self::F64(_) => "float64",
+            Self::I8(_) => "int8",
+            Self::U8(_) => "uint8",
+            Self::I64(_) => "int64",
+        }
+    }
+
+    fn values(&self) -> Box<dyn Iterator<Item = f64> + '_> {
+        match self {
+            Self::F32(data) => Box::new(data.iter().map(|&x| x as f64)),
+            Self::F64(data) => Box::new(data.iter().copied()),
+            Self::I8(data) => Box::new(data.iter().map(|&x| x as f64)),
+            Self::U8(data) => Box::new(data.iter().map(|&x| x as f64)),
+            Self::I64(data) => Box::new(data.iter().map(|&x| x as f64)),
+        }
+    }
+
+    /// Same statistics as `TensorStats::new`, computed without copying the data.
+    pub(crate) fn stats(&self, shape: Vec<usize>, dtype: String) -> TensorStats {
+        let element_count = self.len();
+        if element_count == 0 {
+            return TensorStats::new(&[], shape, dtype);
+        }
+
+        let n = element_count as f64;
+        let mean = self.values().sum::<f64>() / n;
+        let variance = self.values().map(|x| (x - mean).powi(2)).sum::<f64>() / n;
+        let (min, max) = self
+            .values()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), x| {
+                (min.min(x), max.max(x))
+            });
+
+        TensorStats {
+            mean,
+            std: variance.sqrt(),
+            min,
+            max,
+            shape,
+            dtype,
+            element_count,
+        }
+    }
+
+    /// Whether every element pair is within `margin`.
+    pub(crate) fn equals(&self, other: &TensorView, margin: &Margin) -> bool {
+        self.len() == other.len()
+            && self
+                .values()
+                .zip(other.values())
+                .all(|(a, b)| margin.allows(a, b))
+    }
+}
+
+/// Compare two tensors the way `diffai_core` compares tensor-like values.
+///
+/// A tensor that gains NaN or infinite values is reported as
+/// `TensorNanIntroduced` or `TensorInfIntroduced` with its statistics and
+/// their counts. With `extended`, statistics of the result include every
+/// `ExtendedStats`, which needs a copy of the values.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn diff_tensors(
+    path: String,
+    old: &TensorView,
+    new: &TensorView,
+    old_shape: Vec<usize>,
+    new_shape: Vec<usize>,
+    dtype: Option<String>,
+    margin: &Margin,
+    extended: bool,
+) -> Option<DiffEntry> {
+    if old_shape != new_shape {
+        return Some(DiffResult::TensorShapeChanged(path, old_shape, new_shape).into());
+    }
+    if old.equals(new, margin) {
+        return None;
+    }
+
+    let old_stats = old.stats(
+        old_shape,
+        dtype.clone().unwrap_or_else(|| old.dtype().to_string()),
+    );
+    let new_stats = new.stats(new_shape, dtype.unwrap_or_else(|| new.dtype().to_string()));
+    let (old_extended, new_extended) = match extended {
+        true => (
+            ExtendedStats::new(old.values().collect()),
+            ExtendedStats::new(new.values().collect()),
+        ),
+        false => (
+            ExtendedStats::counts(old.values()),
+            ExtendedStats::counts(new.values()),
+        ),
+    };
+
+    let introduced = Introduced::between(&old_extended, &new_extended, None);
+    if introduced.is_some() || stats_changed_significantly(&old_stats, &new_stats) {
+        let mut entry = DiffEntry::tensor_stats(
+            path,
+            old_stats,
+            new_stats,
+            (old_extended, new_extended),
+            None,
+        );
+        // The counts alone are only worth reporting when they grew
+        if !extended && introduced.is_none() {
+            entry.extended = None;
+        }
+        Some(entry)
+    } else {
+        Some(DiffResult::TensorDataChanged(path, old_stats.mean, new_stats.mean).into())
+    }
+}
+
+// Same 1% relative threshold `diffai_core` uses for tensor statistics.
+fn stats_changed_significantly(old_stats: &TensorStats, new_stats: &TensorStats) -> bool {
+    let mean_change = (old_stats.mean - new_stats.mean).abs() / old_stats.mean.abs().max(1e-8);
+    let std_change = (old_stats.std - new_stats.std).abs() / old_stats.std.abs().max(1e-8);
+
+    mean_change > 0.01 || std_change > 0.01
+}
@@ -0,0 +1,234 @@
+use diffai_core::{DiffResult, TensorStats};
+use napi_derive::napi;
+use serde_json::Value;
+
+use crate::error::{DiffaiError, DiffaiResult, ErrorCode};
+use crate::pattern::{JsPathPattern, PathPattern};
+use crate::result_path;
+
+/// Tolerance for the numbers at some paths, overriding the tolerance options
+/// it sets
+#[napi(object, object_to_js = false)]
+pub struct JsEpsilonRule {
+    /// Glob or regular expression matched against result paths; a rule also
+    /// covers everything below the paths it matches
+    #[napi(ts_type = "string | RegExp")]
+    pub pattern: JsPathPattern,
+
+    /// Absolute tolerance for the matching paths
+    pub epsilon: Option<f64>,
+
+    /// How numbers at the matching paths are compared, like `toleranceMode`.
+    /// Defaults to "absolute" when `epsilon` is set, and to `toleranceMode`
+    /// otherwise
+    #[napi(ts_type = "'absolute' | 'relative' | 'numpy' | 'ulp'")]
+    pub tolerance_mode: Option<String>,
+
+    /// Relative tolerance for the matching paths
+    pub rtol: Option<f64>,
+
+    /// Maximum ULP distance for the matching paths
+    pub max_ulps: Option<f64>,
+}
+
+/// How two numbers are compared.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+enum Mode {
+    /// `|a - b| <= epsilon`
+    #[default]
+    Absolute,
+    /// `|a - b| <= rtol * max(|a|, |b|)`
+    Relative,
+    /// `|a - b| <= epsilon + rtol * |b|`, like `numpy.isclose(a, b)`
+    Numpy,
+    /// At most `maxUlps` representable float64 values between `a` and `b`
+    Ulp,
+}
+
+impl Mode {
+    fn parse(name: &str) -> DiffaiResult<Self> {
+        match name.to_lowercase().as_str() {
+            "absolute" => Ok(Self::Absolute),
+            "relative" => Ok(Self::Relative),
+            "numpy" => Ok(Self::Numpy),
+            "ulp" => Ok(Self::Ulp),
+            _ => Err(DiffaiError::new(
+                ErrorCode::InvalidOption,
+                format!(
+                    "Invalid tolerance mode: '{name}'. Expected one of: absolute, relative, numpy, ulp"
+                ),
+            )),
+        }
+    }
+}
+
+// numpy.isclose defaults
+const NUMPY_ATOL: f64 = 1e-8;
+const NUMPY_RTOL: f64 = 1e-5;
+
+/// The tolerance for the numbers at one path.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct Margin {
+    mode: Mode,
+    epsilon: Option<f64>,
+    rtol: Option<f64>,
+    max_ulps: Option<f64>,
+}
+
+impl Margin {
+    /// This margin with the tolerance options that are set replaced.
+    pub(crate) fn with(
+        mut self,
+        epsilon: Option<f64>,
+        mode: Option<&str>,
+        rtol: Option<f64>,
+        max_ulps: Option<f64>,
+    ) -> DiffaiResult<Self> {
+        for (name, value) in [("epsilon", epsilon), ("rtol", rtol), ("maxUlps", max_ulps)] {
+            if value.is_some_and(|value| value.is_nan() || value < 0.0) {
+                return Err(DiffaiError::new(
+                    ErrorCode::InvalidOption,
+                    format!("Invalid {name}: must not be negative"),
+                ));
+            }
+        }
+
+        if let Some(mode) = mode {
+            self.mode = Mode::parse(mode)?;
+        }
+        self.epsilon = epsilon.or(self.epsilon);
+        self.rtol = rtol.or(self.rtol);
+        self.max_ulps = max_ulps.or(self.max_ulps);
+
+        match self.mode {
+            Mode::Relative if self.rtol.is_none() => Err(DiffaiError::new(
+                ErrorCode::InvalidOption,
+                "The relative tolerance mode requires rtol",
+            )),
+            Mode::Ulp if self.max_ulps.is_none() => Err(DiffaiError::new(
+                ErrorCode::InvalidOption,
+                "The ulp tolerance mode requires maxUlps",
+            )),
+            _ => Ok(self),
+        }
+    }
+
+    /// Whether `old` and `new` are close enough to be considered equal.
+    pub(crate) fn allows(&self, old: f64, new: f64) -> bool {
+        if old == new {
+            return true;
+        }
+        // NaN fails every comparison below
+        let difference = (old - new).abs();
+        match self.mode {
+            Mode::Absolute => self.epsilon.is_some_and(|epsilon| difference <= epsilon),
+            Mode::Relative => difference <= self.rtol.unwrap_or(0.0) * old.abs().max(new.abs()),
+            Mode::Numpy => {
+                difference
+                    <= self.epsilon.unwrap_or(NUMPY_ATOL)
+                        + self.rtol.unwrap_or(NUMPY_RTOL) * new.abs()
+            }
+            Mode::Ulp => {
+                !old.is_nan()
+                    && !new.is_nan()
+                    && ulps_between(old, new) <= self.max_ulps.unwrap_or(0.0)
+            }
+        }
+    }
+}
+
+/// Number of float64 values from `a` to `b`, counting `-0.0` and `0.0` as one.
+fn ulps_between(a: f64, b: f64) -> f64 {
+    // Map the bit patterns onto integers in the same order as the floats
+    let ordered = |x: f64| {
+        let bits = x.to_bits() as i64;
+        if bits < 0 {
+            i64::MIN - bits
+        } else {
+            bits
+        }
+    };
+    (i128::from(ordered(a)) - i128::from(ordered(b))).unsigned_abs() as f64
+}
+
+/// Decides which numeric changes are small enough to be ignored.
+#[derive(Default)]
+pub(crate) struct Tolerance {
+    default: Margin,
+    rules: Vec<(PathPattern, Margin)>,
+}
+
+impl Tolerance {
+    pub(crate) fn new(default: Margin, rules: Vec<JsEpsilonRule>) -> DiffaiResult<Self> {
+        let rules = rules
+            .into_iter()
+            .map(|rule| {
+                // An epsilon alone means an absolute tolerance, whatever the
+                // default mode is
+                let mode = match (&rule.tolerance_mode, rule.epsilon) {
+                    (Some(mode), _) => Some(mode.as_str()),
+                    (None, Some(_)) => Some("absolute"),
+                    (None, None) => None,
+                };
+                let margin = default.with(rule.epsilon, mode, rule.rtol, rule.max_ulps)?;
+                Ok((PathPattern::compile(rule.pattern)?, margin))
+            })
+            .collect::<DiffaiResult<_>>()?;
+        Ok(Self { default, rules })
+    }
+
+    /// The epsilon the core can apply itself, which it only can for a single
+    /// absolute tolerance.
+    pub(crate) fn core_epsilon(&self) -> Option<f64> {
+        match (self.rules.is_empty(), self.default.mode) {
+            (true, Mode::Absolute) => self.default.epsilon,
+            _ => None,
+        }
+    }
+
+    /// Margin of the most specific rule matching `path`, or the default one.
+    /// Of equally specific rules, the first one wins.
+    pub(crate) fn margin_for(&self, path: &str) -> Margin {
+        let mut best: Option<&(PathPattern, Margin)> = None;
+        for rule in self
+            .rules
+            .iter()
+            .filter(|(pattern, _)| pattern.matches_within(path))
+        {
+            if best.is_none_or(|(best, _)| rule.0.specificity() > best.specificity()) {
+                best = Some(rule);
+            }
+        }
+        best.map_or(self.default, |&(_, margin)| margin)
+    }
+
+    /// Whether every number a result reports changed stays within the
+    /// tolerance of its path, covering plain values and tensor statistics.
+    pub(crate) fn absorbs(&self, result: &DiffResult) -> bool {
+        let margin = self.margin_for(result_path(result));
+
+        match result {
+            DiffResult::Modified(_, Value::Number(old), Value::Number(new)) => {
+                match (old.as_f64(), new.as_f64()) {
+                    (Some(old), Some(new)) => margin.allows(old, new),
+                    _ => false,
+                }
+            }
+            DiffResult::TensorStatsChanged(_, old, new) => stats_close(old, new, &margin),
+            DiffResult::TensorDataChanged(_, old_mean, new_mean) => {
+                margin.allows(*old_mean, *new_mean)
+            }
+            _ => false,
+        }
+    }
+}
+
+fn stats_close(old: &TensorStats, new: &TensorStats, margin: &Margin) -> bool {
+    old.shape == new.shape
+        && old.dtype == new.dtype
+        && old.element_count == new.element_count
+        && margin.allows(old.mean, new.mean)
+        && margin.allows(old.std, new.std)
+        && margin.allows(old.min, new.min)
+        && margin.allows(old.max, new.max)
+}
@@ -0,0 +1,100 @@
+const { getEventListeners } = require('events');
+const diffai = require('../index.js');
+const { tempDir, writeSafetensors } = require('./helpers');
+
+describe('async API', () => {
+    test('diffAsync resolves with the same results as diff', async () => {
+        const old = { a: 1, b: { c: [1, 2, 3] } };
+        const newObj = { a: 2, b: { c: [1, 2, 4] }, d: true };
+        const expected = diffai.diff(old, newObj);
+        const results = await diffai.diffAsync(old, newObj);
+        expect(results).toEqual(expected);
+    });
+
+    test('diffAsync applies options', async () => {
+        const results = await diffai.diffAsync({ value: 1.0 }, { value: 1.0001 }, { epsilon: 0.001 });
+        expect(results.length).toBe(0);
+    });
+
+    test('diffPathsAsync matches diffPaths', async () => {
+        const dir = tempDir();
+        const a = writeSafetensors(dir, 'a.safetensors', { w: [1, 2, 3] });
+        const b = writeSafetensors(dir, 'b.safetensors', { w: [1, 2, 4] });
+        const expected = diffai.diffPaths(a, b);
+        const results = await diffai.diffPathsAsync(a, b);
+        expect(expected.length).toBeGreaterThan(0);
+        expect(results).toEqual(expected);
+    });
+
+    test('diffPathsAsync rejects for missing files', async () => {
+        await expect(diffai.diffPathsAsync('missing_a.safetensors', 'missing_b.safetensors'))
+            .rejects.toThrow();
+    });
+
+    describe('cancellation', () => {
+        test('resolves normally with a signal that is never aborted', async () => {
+            const dir = tempDir();
+            const a = writeSafetensors(dir, 'a.safetensors', { w: [1, 2, 3] });
+            const b = writeSafetensors(dir, 'b.safetensors', { w: [1, 2, 4] });
+            const controller = new AbortController();
+            const results = await diffai.diffPathsAsync(a, b, {}, controller.signal);
+            expect(results).toEqual(diffai.diffPaths(a, b));
+        });
+
+        test('removes its abort listener once done', async () => {
+            const dir = tempDir();
+            const a = writeSafetensors(dir, 'a.safetensors', { w: [1, 2, 3] });
+            const b = writeSafetensors(dir, 'b.safetensors', { w: [1, 2, 4] });
+            const missing = `${dir}/missing.safetensors`;
+            const { signal } = new AbortController();
+
+            for (let i = 0; i < 5; i++) {
+                await diffai.diffPathsAsync(a, b, {}, signal);
+                await diffai.diffPathsAsync(a, missing, {}, signal).catch(() => {});
+            }
+            for await (const batch of diffai.diffPathsStream(a, b, {}, signal)) {
+                expect(batch.length).toBeGreaterThan(0);
+            }
+            for await (const batch of diffai.diffPathsStream(a, b, { batchSize: 1 }, signal)) {
+                expect(batch).toHaveLength(1);
+                break;
+            }
+            expect(getEventListeners(signal, 'abort')).toHaveLength(0);
+        });
+
+        test('rejects with DIFFAI_CANCELLED when the signal is already aborted', async () => {
+            const dir = tempDir();
+            writeSafetensors(dir, 'old/a.safetensors', { w: [1, 2, 3] });
+            writeSafetensors(dir, 'new/a.safetensors', { w: [1, 2, 4] });
+            const controller = new AbortController();
+            controller.abort();
+            await expect(
+                diffai.diffPathsAsync(`${dir}/old`, `${dir}/new`, {}, controller.signal)
+            ).rejects.toMatchObject({ code: 'DIFFAI_CANCELLED' });
+        });
+    });
+
+    describe('progress', () => {
+        test('reports files and bytes until the walk is complete', async () => {
+            const dir = tempDir();
+            writeSafetensors(dir, 'old/a.safetensors', { w: [1, 2, 3] });
+            writeSafetensors(dir, 'new/a.safetensors', { w: [1, 2, 4] });
+            writeSafetensors(dir, 'old/sub/b.safetensors', { w: [1] });
+            writeSafetensors(dir, 'new/sub/b.safetensors', { w: [2] });
+            writeSafetensors(dir, 'new/c.safetensors', { w: [5] });
+
+            const events = [];
+            await diffai.diffPathsAsync(`${dir}/old`, `${dir}/new`, {
+                onProgress: (progress) => events.push(progress),
+            });
+            await new Promise((resolve) => setTimeout(resolve, 20));
+
+            expect(events.length).toBeGreaterThanOrEqual(1);
+            const last = events[events.length - 1];
+            expect(last.filesTotal).toBe(3);
+            expect(last.filesVisited).toBe(3);
+            expect(last.bytesTotal).toBeGreaterThan(0);
+            expect(last.bytesRead).toBe(last.bytesTotal);
+        });
+    });
+});
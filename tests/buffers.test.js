@@ -0,0 +1,65 @@
+const fs = require('fs');
+const path = require('path');
+const diffai = require('../index.js');
+const { safetensorsBuffer, tempDir } = require('./helpers');
+
+describe('diffBuffers()', () => {
+    test('matches diffPaths for safetensors content', () => {
+        const oldBuf = safetensorsBuffer({ w: [1, 2, 3], b: [0.5] });
+        const newBuf = safetensorsBuffer({ w: [1, 2, 4], b: [0.5] });
+        const dir = tempDir();
+        fs.writeFileSync(path.join(dir, 'a.safetensors'), oldBuf);
+        fs.writeFileSync(path.join(dir, 'b.safetensors'), newBuf);
+
+        const expected = diffai.diffPaths(path.join(dir, 'a.safetensors'), path.join(dir, 'b.safetensors'));
+        expect(diffai.diffBuffers(oldBuf, newBuf)).toEqual(expected);
+        expect(diffai.diffBuffers(oldBuf, newBuf, { format: 'safetensors' })).toEqual(expected);
+    });
+
+    test('matches diffPaths for PyTorch pickle content', () => {
+        const oldBuf = Buffer.concat([Buffer.from([0x80, 0x02]), Buffer.from('conv1.weight conv1.bias epoch')]);
+        const newBuf = Buffer.concat([Buffer.from([0x80, 0x02]), Buffer.from('conv1.weight fc.weight fc.bias optimizer')]);
+        const dir = tempDir();
+        fs.writeFileSync(path.join(dir, 'a.pt'), oldBuf);
+        fs.writeFileSync(path.join(dir, 'b.pt'), newBuf);
+
+        const expected = diffai.diffPaths(path.join(dir, 'a.pt'), path.join(dir, 'b.pt'));
+        expect(expected.length).toBeGreaterThan(0);
+        expect(diffai.diffBuffers(oldBuf, newBuf)).toEqual(expected);
+    });
+
+    test('returns no differences for identical buffers', () => {
+        const buf = safetensorsBuffer({ w: [1, 2, 3] });
+        expect(diffai.diffBuffers(buf, buf)).toEqual([]);
+    });
+
+    test('throws when the format cannot be detected', () => {
+        const buf = Buffer.from('not a model');
+        expect(() => diffai.diffBuffers(buf, buf)).toThrow('Unable to detect');
+    });
+
+    test('throws for an unknown format name', () => {
+        const buf = safetensorsBuffer({ w: [1] });
+        expect(() => diffai.diffBuffers(buf, buf, { format: 'onnx' })).toThrow('Invalid format');
+    });
+
+    test('rejects NumPy and MATLAB buffers instead of comparing them as equal', () => {
+        const npy = Buffer.concat([Buffer.from('\x93NUMPY', 'latin1'), Buffer.from([1, 0])]);
+        const mat = Buffer.from('MATLAB 5.0 MAT-file');
+        for (const [buf, options] of [[npy], [mat], [mat, { format: 'npy' }]]) {
+            try {
+                diffai.diffBuffers(buf, buf, options);
+                throw new Error('expected diffBuffers to throw');
+            } catch (error) {
+                expect(error.code).toBe('DIFFAI_UNSUPPORTED_FORMAT');
+                expect(error.message).toContain('is not supported yet');
+            }
+        }
+    });
+
+    test('throws for a corrupt safetensors header', () => {
+        const buf = safetensorsBuffer({ w: [1, 2] });
+        expect(() => diffai.diffBuffers(buf.subarray(0, buf.length - 4), buf, { format: 'safetensors' }))
+            .toThrow('Invalid safetensors header');
+    });
+});
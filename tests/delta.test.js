@@ -0,0 +1,120 @@
+const diffai = require('../index.js');
+const { allResultVariants, tensorStats } = require('./helpers');
+
+const byPath = (a, b) => (a.path + a.diffType).localeCompare(b.path + b.diffType);
+
+describe('invert()', () => {
+    test('matches diffing the other way round', () => {
+        const a = { name: 'a', layers: [1, 2, 3], extra: { x: 1 } };
+        const b = { name: 'b', layers: [1, 2], added: true };
+        const inverted = diffai.invert(diffai.diff(a, b));
+        expect(inverted.sort(byPath)).toEqual(diffai.diff(b, a).sort(byPath));
+    });
+
+    test('swaps old and new of every diff type', () => {
+        const inverted = diffai.invert(allResultVariants());
+        const find = (type) => inverted.find((r) => r.diffType === type);
+
+        expect(inverted.filter((r) => r.path === 'a')).toEqual([{ diffType: 'Removed', path: 'a', value: 1 }]);
+        expect(inverted.filter((r) => r.path === 'b')).toEqual([{ diffType: 'Added', path: 'b', newValue: 'gone' }]);
+        expect(find('Modified')).toMatchObject({ oldValue: 2, newValue: 1 });
+        expect(find('TensorShapeChanged')).toMatchObject({ oldShape: [3, 3], newShape: [2, 3] });
+        expect(find('TensorStatsChanged')).toMatchObject({ oldStats: tensorStats(0.4), newStats: tensorStats(0.1) });
+        expect(find('TensorDataChanged')).toMatchObject({ oldMean: 0.2, newMean: 0.1 });
+        expect(find('WeightSignificantChange')).toMatchObject({ changeMagnitude: 0.75 });
+        expect(find('OptimizerChanged')).toMatchObject({ oldString: 'adam', newString: 'sgd' });
+        expect(find('LossChange')).toMatchObject({ oldFloat: 0.3, newFloat: 0.5 });
+    });
+
+    test('is its own inverse', () => {
+        expect(diffai.invert(diffai.invert(allResultVariants()))).toEqual(allResultVariants());
+    });
+});
+
+describe('compose()', () => {
+    const epoch1 = { lr: 0.1, layers: [{ w: 1 }, { w: 2 }], optimizer: { name: 'sgd' } };
+    const epoch2 = { lr: 0.05, layers: [{ w: 1.5 }, { w: 2 }, { w: 3 }], optimizer: { name: 'sgd', momentum: 0.9 } };
+    const epoch3 = { lr: 0.01, layers: [{ w: 1.5 }, { w: 2.5 }], scheduler: 'cosine' };
+
+    const edits = (results) =>
+        results.filter((r) => ['Added', 'Removed', 'Modified', 'TypeChanged'].includes(r.diffType));
+
+    test('produces the delta from the first to the last value', () => {
+        const composed = diffai.compose(diffai.diff(epoch1, epoch2), diffai.diff(epoch2, epoch3));
+
+        expect(diffai.patch(epoch1, composed)).toEqual(epoch3);
+        expect(edits(composed).sort(byPath)).toEqual(edits(diffai.diff(epoch1, epoch3)).sort(byPath));
+    });
+
+    test('drops changes that cancel out', () => {
+        expect(edits(diffai.compose(diffai.diff(epoch1, epoch2), diffai.diff(epoch2, epoch1)))).toEqual([]);
+    });
+
+    test('folds changes into added and removed values', () => {
+        expect(edits(diffai.compose(diffai.diff({}, { opt: { lr: 1 } }), diffai.diff({ opt: { lr: 1 } }, { opt: { lr: 2 } })))).toEqual([
+            { diffType: 'Added', path: 'opt', newValue: { lr: 2 } },
+        ]);
+        expect(
+            edits(diffai.compose(diffai.diff({ opt: { lr: 1, m: 1 } }, { opt: { lr: 2, m: 1 } }), diffai.diff({ opt: { lr: 2, m: 1 } }, {})))
+        ).toEqual([{ diffType: 'Removed', path: 'opt', value: { lr: 1, m: 1 } }]);
+    });
+
+    test('chains tensor results', () => {
+        const composed = diffai.compose(
+            [
+                { diffType: 'TensorStatsChanged', path: 'fc', oldStats: tensorStats(0.1), newStats: tensorStats(0.2) },
+                { diffType: 'TensorShapeChanged', path: 'fc', oldShape: [2, 3], newShape: [3, 3] },
+                { diffType: 'WeightSignificantChange', path: 'fc', changeMagnitude: 0.5 },
+            ],
+            [
+                { diffType: 'TensorStatsChanged', path: 'fc', oldStats: tensorStats(0.2), newStats: tensorStats(0.4) },
+                { diffType: 'TensorShapeChanged', path: 'fc', oldShape: [3, 3], newShape: [2, 3] },
+                { diffType: 'WeightSignificantChange', path: 'fc', changeMagnitude: 0.25 },
+                { diffType: 'TensorDataChanged', path: 'conv', oldMean: 0, newMean: 1 },
+            ]
+        );
+
+        expect(composed).toEqual([
+            { diffType: 'TensorStatsChanged', path: 'fc', oldStats: tensorStats(0.1), newStats: tensorStats(0.4) },
+            { diffType: 'WeightSignificantChange', path: 'fc', changeMagnitude: 0.75 },
+            { diffType: 'TensorDataChanged', path: 'conv', oldMean: 0, newMean: 1 },
+        ]);
+    });
+
+    test('rebases indices past elements inserted or removed before them', () => {
+        const inserted = [{ diffType: 'Added', path: 'a[0]', newValue: 0 }];
+        const afterInsert = diffai.compose(inserted, diffai.diff({ a: [0, 1, 2, 3] }, { a: [0, 1, 2, 9] }));
+        expect(edits(afterInsert).sort(byPath)).toEqual([
+            { diffType: 'Added', path: 'a[0]', newValue: 0 },
+            { diffType: 'Modified', path: 'a[2]', oldValue: 3, newValue: 9 },
+        ]);
+        expect(diffai.patch({ a: [1, 2, 3] }, afterInsert)).toEqual({ a: [0, 1, 2, 9] });
+
+        const removed = [{ diffType: 'Removed', path: 'a[0]', value: 0 }];
+        const afterRemoval = diffai.compose(removed, [{ diffType: 'Modified', path: 'a[2]', oldValue: 3, newValue: 9 }]);
+        expect(edits(afterRemoval)).toContainEqual({ diffType: 'Modified', path: 'a[3]', oldValue: 3, newValue: 9 });
+        expect(diffai.patch({ a: [0, 1, 2, 3] }, afterRemoval)).toEqual({ a: [1, 2, 9] });
+    });
+
+    test('composes deltas that both change the length of an array', () => {
+        const inserted = [{ diffType: 'Added', path: 'a[0]', newValue: 0 }];
+        const reshaped = diffai.compose(inserted, [
+            { diffType: 'Removed', path: 'a[1]', value: 1 },
+            { diffType: 'Modified', path: 'a[2]', oldValue: 2, newValue: 5 },
+            { diffType: 'Added', path: 'a[3]', newValue: 7 },
+        ]);
+        expect(diffai.patch({ a: [1, 2, 3] }, reshaped)).toEqual({ a: [0, 5, 3, 7] });
+        expect(diffai.compose(inserted, [{ diffType: 'Removed', path: 'a[0]', value: 0 }])).toEqual([]);
+
+        const tail = diffai.compose(diffai.diff([1, 2, 3], [1, 2]), diffai.diff([1, 2], [1, 2, 5]));
+        expect(tail).toEqual([{ diffType: 'Modified', path: '[2]', oldValue: 3, newValue: 5 }]);
+    });
+
+    test('rejects results that do not follow each other', () => {
+        const added = [{ diffType: 'Added', path: 'a', newValue: 1 }];
+        expect(() => diffai.compose(added, added)).toThrow('Cannot compose the Added and Added results');
+        expect(() =>
+            diffai.compose(added, [{ diffType: 'Modified', path: 'a.b', oldValue: 1, newValue: 2 }])
+        ).toThrow('do not follow each other');
+    });
+});
@@ -1,4 +1,5 @@
 const diffai = require('../index.js');
+const { allResultVariants } = require('./helpers');
 
 describe('diff()', () => {
     describe('Basic API', () => {
@@ -13,6 +14,15 @@ describe('diff()', () => {
         test('formatOutput function exists', () => {
             expect(typeof diffai.formatOutput).toBe('function');
         });
+
+        test('DiffType enumerates every result type', () => {
+            expect(diffai.DiffType.Added).toBe('Added');
+            const types = Object.values(diffai.DiffType);
+            expect(types.length).toBe(17);
+            allResultVariants().forEach((result) => {
+                expect(types).toContain(result.diffType);
+            });
+        });
     });
 
     describe('Basic Diff Operations', () => {
@@ -125,6 +135,82 @@ describe('diff()', () => {
         });
     });
 
+    describe('Result Round-Trip', () => {
+        // Field order of each variant's tuple in the core's JSON output
+        const tupleFields = {
+            Added: ['newValue'],
+            Removed: ['value'],
+            Modified: ['oldValue', 'newValue'],
+            TypeChanged: ['oldValue', 'newValue'],
+            TensorShapeChanged: ['oldShape', 'newShape'],
+            TensorStatsChanged: ['oldStats', 'newStats'],
+            TensorDataChanged: ['oldMean', 'newMean'],
+            ModelArchitectureChanged: ['oldString', 'newString'],
+            WeightSignificantChange: ['changeMagnitude'],
+            ActivationFunctionChanged: ['oldString', 'newString'],
+            LearningRateChanged: ['oldFloat', 'newFloat'],
+            OptimizerChanged: ['oldString', 'newString'],
+            LossChange: ['oldFloat', 'newFloat'],
+            AccuracyChange: ['oldFloat', 'newFloat'],
+            ModelVersionChanged: ['oldString', 'newString'],
+        };
+
+        const snakeStats = (stats) => ({
+            mean: stats.mean,
+            std: stats.std,
+            min: stats.min,
+            max: stats.max,
+            shape: stats.shape,
+            dtype: stats.dtype,
+            element_count: stats.elementCount,
+        });
+
+        test('every diff type converts back through formatOutput', () => {
+            const results = allResultVariants();
+            const formatted = JSON.parse(diffai.formatOutput(results, 'json'));
+            expect(formatted.length).toBe(results.length);
+
+            results.forEach((result, i) => {
+                const fields = tupleFields[result.diffType];
+                const expected = [
+                    result.path,
+                    ...fields.map((field) => (field.endsWith('Stats') ? snakeStats(result[field]) : result[field])),
+                ];
+                expect(formatted[i][result.diffType]).toEqual(expected);
+            });
+        });
+
+        test('every diff type formats as yaml and diffai', () => {
+            const results = allResultVariants();
+            expect(() => diffai.formatOutput(results, 'yaml')).not.toThrow();
+            expect(() => diffai.formatOutput(results, 'diffai')).not.toThrow();
+        });
+
+        test('rejects results missing required fields', () => {
+            expect(() => diffai.formatOutput([{ diffType: 'TensorStatsChanged', path: 'x' }], 'json'))
+                .toThrow('TensorStatsChanged result must have old_stats');
+        });
+
+        test('keeps tensor sizes above 2^32 exact', () => {
+            const big = 5000000000;
+            const result = {
+                diffType: 'TensorShapeChanged',
+                path: 'embed.weight',
+                oldShape: [big, 4096],
+                newShape: [big + 1, 4096],
+            };
+            const formatted = JSON.parse(diffai.formatOutput([result], 'json'));
+            expect(formatted[0].TensorShapeChanged).toEqual(['embed.weight', [big, 4096], [big + 1, 4096]]);
+        });
+
+        test('rejects tensor sizes that are not safe integers', () => {
+            const result = { diffType: 'TensorShapeChanged', path: 'x', oldShape: [1.5], newShape: [2] };
+            expect(() => diffai.formatOutput([result], 'json')).toThrow('Invalid tensor size 1.5');
+            result.oldShape = [2 ** 53];
+            expect(() => diffai.formatOutput([result], 'json')).toThrow('expected a non-negative safe integer');
+        });
+    });
+
     describe('Error Handling', () => {
         test('handles empty objects', () => {
             const results = diffai.diff({}, {});
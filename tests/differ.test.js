@@ -0,0 +1,55 @@
+const diffai = require('../index.js');
+const { tempDir, writeSafetensors } = require('./helpers');
+
+describe('Differ', () => {
+    test('diff matches the free function with the same options', () => {
+        const options = { epsilon: 0.001, ignoreKeysRegex: '^timestamp$' };
+        const differ = new diffai.Differ(options);
+        const old = { value: 1.0, timestamp: 1, name: 'a' };
+        const newObj = { value: 1.0001, timestamp: 2, name: 'b' };
+        expect(differ.diff(old, newObj)).toEqual(diffai.diff(old, newObj, options));
+        expect(differ.diff(old, newObj).length).toBe(1);
+    });
+
+    test('can be reused for many comparisons', () => {
+        const differ = new diffai.Differ();
+        for (let i = 0; i < 100; i++) {
+            expect(differ.diff({ i }, { i: i + 1 }).length).toBe(1);
+        }
+    });
+
+    test('diffPaths matches the free function', () => {
+        const dir = tempDir();
+        const a = writeSafetensors(dir, 'a.safetensors', { w: [1, 2, 3] });
+        const b = writeSafetensors(dir, 'b.safetensors', { w: [1, 2, 4] });
+        const differ = new diffai.Differ();
+        expect(differ.diffPaths(a, b)).toEqual(diffai.diffPaths(a, b));
+    });
+
+    test('reports progress on every diffPaths call', async () => {
+        const dir = tempDir();
+        const a = writeSafetensors(dir, 'a.safetensors', { w: [1, 2, 3] });
+        const b = writeSafetensors(dir, 'b.safetensors', { w: [1, 2, 4] });
+        const events = [];
+        const differ = new diffai.Differ({ onProgress: (progress) => events.push(progress) });
+        differ.diffPaths(a, b);
+        differ.diffPaths(a, b);
+        await new Promise((resolve) => setTimeout(resolve, 20));
+
+        expect(events.filter((e) => e.filesVisited === 1).length).toBe(2);
+    });
+
+    test('format uses the outputFormat option', () => {
+        const old = { a: 1 };
+        const newObj = { a: 2 };
+        const json = new diffai.Differ({ outputFormat: 'json' });
+        const results = json.diff(old, newObj);
+        expect(json.format(results)).toBe(diffai.formatOutput(results, 'json'));
+        expect(new diffai.Differ().format(results)).toBe(diffai.formatOutput(results, 'diffai'));
+    });
+
+    test('rejects invalid options at construction', () => {
+        expect(() => new diffai.Differ({ ignoreKeysRegex: '[' })).toThrow('Invalid regex');
+        expect(() => new diffai.Differ({ outputFormat: 'xml' })).toThrow('Invalid output format');
+    });
+});
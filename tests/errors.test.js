@@ -0,0 +1,93 @@
+const fs = require('fs');
+const path = require('path');
+const diffai = require('../index.js');
+const { safetensorsBuffer, tempDir, writeSafetensors } = require('./helpers');
+
+function thrown(fn) {
+    try {
+        fn();
+    } catch (e) {
+        return e;
+    }
+    throw new Error('expected an error to be thrown');
+}
+
+describe('structured errors', () => {
+    test('exports every error code', () => {
+        expect(diffai.ErrorCode.ParseError).toBe('DIFFAI_PARSE_ERROR');
+        expect(diffai.ErrorCode.UnsupportedFormat).toBe('DIFFAI_UNSUPPORTED_FORMAT');
+        expect(diffai.ErrorCode.InvalidOption).toBe('DIFFAI_INVALID_OPTION');
+    });
+
+    test('missing files report DIFFAI_FILE_NOT_FOUND with the path', () => {
+        const dir = tempDir();
+        const missing = path.join(dir, 'missing.safetensors');
+        const error = thrown(() => diffai.diffPaths(missing, missing));
+        expect(error).toBeInstanceOf(Error);
+        expect(error.code).toBe('DIFFAI_FILE_NOT_FOUND');
+        expect(error.path).toBe(missing);
+        expect(error.cause).toBeInstanceOf(Error);
+    });
+
+    test('unknown file extensions report DIFFAI_UNSUPPORTED_FORMAT', () => {
+        const dir = tempDir();
+        const file = path.join(dir, 'model.onnx');
+        fs.writeFileSync(file, 'x');
+        const error = thrown(() => diffai.diffPaths(file, file));
+        expect(error.code).toBe('DIFFAI_UNSUPPORTED_FORMAT');
+        expect(error.path).toBe(file);
+    });
+
+    test('corrupt model files report DIFFAI_PARSE_ERROR with the cause', () => {
+        const dir = tempDir();
+        const good = writeSafetensors(dir, 'good.safetensors', { w: [1, 2] });
+        const bad = path.join(dir, 'bad.safetensors');
+        fs.writeFileSync(bad, safetensorsBuffer({ w: [1, 2] }).subarray(0, 20));
+        const error = thrown(() => diffai.diffPaths(bad, good));
+        expect(error.code).toBe('DIFFAI_PARSE_ERROR');
+        expect(error.path).toBe(bad);
+        expect(error.cause.message).toContain('Invalid safetensors header');
+    });
+
+    test('shapes too large for their byte length report DIFFAI_PARSE_ERROR', () => {
+        // 2^32 * 2^32 elements wrap around to 0 bytes without overflow checks
+        let json = JSON.stringify({ w: { dtype: 'F32', shape: [2 ** 32, 2 ** 32], data_offsets: [0, 0] } });
+        json += ' '.repeat((8 - (json.length % 8)) % 8);
+        const len = Buffer.alloc(8);
+        len.writeBigUInt64LE(BigInt(json.length));
+        const buf = Buffer.concat([len, Buffer.from(json)]);
+
+        const error = thrown(() => diffai.diffBuffers(buf, safetensorsBuffer({ w: [1] }), { format: 'safetensors' }));
+        expect(error.code).toBe('DIFFAI_PARSE_ERROR');
+        expect(error.cause.message).toContain("tensor 'w' has a shape of more than 2^64 bytes");
+    });
+
+    test('invalid options report DIFFAI_INVALID_OPTION', () => {
+        const error = thrown(() => diffai.diff({}, {}, { ignoreKeysRegex: '(' }));
+        expect(error.code).toBe('DIFFAI_INVALID_OPTION');
+        expect(error.message).toContain('Invalid regex');
+        expect(error.cause).toBeInstanceOf(Error);
+    });
+
+    test('invalid buffers report format and parse codes', () => {
+        const buf = safetensorsBuffer({ w: [1, 2] });
+        expect(thrown(() => diffai.diffBuffers(buf, buf, { format: 'onnx' })).code)
+            .toBe('DIFFAI_UNSUPPORTED_FORMAT');
+        expect(thrown(() => diffai.diffBuffers(buf.subarray(0, 12), buf)).code)
+            .toBe('DIFFAI_PARSE_ERROR');
+    });
+
+    test('malformed results report DIFFAI_INVALID_ARGUMENT', () => {
+        const error = thrown(() => diffai.formatOutput([{ diffType: 'Modified', path: 'a' }], 'json'));
+        expect(error.code).toBe('DIFFAI_INVALID_ARGUMENT');
+    });
+
+    test('async functions reject with the same structured errors', async () => {
+        const dir = tempDir();
+        const missing = path.join(dir, 'missing.safetensors');
+        await expect(diffai.diffPathsAsync(missing, missing))
+            .rejects.toMatchObject({ code: 'DIFFAI_FILE_NOT_FOUND', path: missing });
+        expect(thrown(() => diffai.diffAsync({}, {}, { outputFormat: 'xml' })).code)
+            .toBe('DIFFAI_INVALID_OPTION');
+    });
+});
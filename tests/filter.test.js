@@ -0,0 +1,136 @@
+const path = require('path');
+const diffai = require('../index.js');
+const { tempDir, writeSafetensors } = require('./helpers');
+
+const paths = (results) => results.map((r) => r.path).sort();
+
+describe('include and exclude', () => {
+    const layer = (q, k, w) => ({ attn: { q, k }, mlp: { w } });
+    const old = { encoder: { layers: [layer(1, 2, 3), layer(1, 2, 3)] }, bn: { num_batches_tracked: 1, w: 1 } };
+    const changed = { encoder: { layers: [layer(5, 2, 4), layer(1, 6, 3)] }, bn: { num_batches_tracked: 2, w: 2 } };
+
+    test('include keeps only matching paths and their children', () => {
+        expect(paths(diffai.diff(old, changed, { include: ['encoder.layers[*].attn.**'] }))).toEqual([
+            'encoder.layers[0].attn.q',
+            'encoder.layers[1].attn.k',
+        ]);
+        expect(paths(diffai.diff(old, changed, { include: ['bn', 'encoder.layers[1]'] }))).toEqual([
+            'bn.num_batches_tracked',
+            'bn.w',
+            'encoder.layers[1].attn.k',
+        ]);
+    });
+
+    test('exclude drops matching paths and their children', () => {
+        expect(paths(diffai.diff(old, changed, { exclude: ['**.num_batches_tracked', '**.mlp'] }))).toEqual([
+            'bn.w',
+            'encoder.layers[0].attn.q',
+            'encoder.layers[1].attn.k',
+        ]);
+    });
+
+    test('negated globs', () => {
+        expect(paths(diffai.diff(old, changed, { include: ['bn', '!**.num_batches_tracked'] }))).toEqual(['bn.w']);
+        expect(paths(diffai.diff(old, changed, { exclude: ['encoder', '!encoder.layers[0].mlp'] }))).toEqual([
+            'bn.num_batches_tracked',
+            'bn.w',
+            'encoder.layers[0].mlp.w',
+        ]);
+    });
+
+    test('combine with the other options', () => {
+        const results = new diffai.Differ({ exclude: ['bn.**'], epsilon: 1.5 }).diff(old, changed);
+        expect(paths(results)).toEqual(['encoder.layers[0].attn.q', 'encoder.layers[1].attn.k']);
+    });
+
+    test('match tensors and files of directory comparisons', () => {
+        const dir = tempDir();
+        writeSafetensors(dir, 'a/model.safetensors', { 'fc.bias': [1, 2, 3], 'bn.running_mean': [0, 0] });
+        writeSafetensors(dir, 'b/model.safetensors', { 'fc.bias': [1, 2, 4], 'bn.running_mean': [1, 1] });
+        writeSafetensors(dir, 'a/optimizer.safetensors', { step: [1] });
+        writeSafetensors(dir, 'b/optimizer.safetensors', { step: [2] });
+        const a = path.join(dir, 'a');
+        const b = path.join(dir, 'b');
+
+        const all = diffai.diffPaths(a, b);
+        expect(all.some((r) => r.path.startsWith('optimizer.safetensors/'))).toBe(true);
+        expect(all.some((r) => r.path.includes('bn.running_mean'))).toBe(true);
+
+        const filtered = diffai.diffPaths(a, b, { exclude: ['optimizer.safetensors', '**.bn.**'] });
+        expect(filtered.length).toBeGreaterThan(0);
+        expect(filtered.every((r) => r.path.startsWith('model.safetensors/tensors.fc.bias.'))).toBe(true);
+
+        const included = diffai.diffPaths(a, b, { include: ['*.safetensors/tensors.bn.**'] });
+        expect(included.length).toBeGreaterThan(0);
+        expect(included.every((r) => r.path.startsWith('model.safetensors/tensors.bn.running_mean.'))).toBe(true);
+    });
+
+    test('skip excluded tensors of single files', () => {
+        const dir = tempDir();
+        const a = writeSafetensors(dir, 'a.safetensors', { 'fc.bias': [1, 2, 3], extra: [1] });
+        const b = writeSafetensors(dir, 'b.safetensors', { 'fc.bias': [1, 2, 3], extra: [2] });
+
+        expect(diffai.diffPaths(a, b).length).toBeGreaterThan(0);
+        expect(diffai.diffPaths(a, b, { exclude: ['tensors.extra'] })).toEqual([]);
+    });
+});
+
+describe('diff type and severity filters', () => {
+    const old = { fc: { weight: { shape: [2], data: [1, 2] } }, lr: 0.1, x: 1 };
+    const changed = { fc: { weight: { shape: [3], data: [1, 2, 3] } }, lr: 0.2, x: 2, y: 1 };
+    const types = (results) => [...new Set(results.map((r) => r.diffType))].sort();
+
+    test('exports the severities', () => {
+        expect(diffai.Severity).toEqual({ Info: 'info', Warning: 'warning', Critical: 'critical' });
+    });
+
+    test('onlyTypes keeps the listed types', () => {
+        const results = diffai.diff(old, changed, { onlyTypes: ['Added', 'LearningRateChanged'] });
+        expect(types(results)).toEqual(['Added', 'LearningRateChanged']);
+        expect(paths(results)).toEqual(['fc.weight.data[2]', 'lr', 'y']);
+    });
+
+    test('excludeTypes drops the listed types', () => {
+        const results = diffai.diff(old, changed, { excludeTypes: ['Modified', 'ModelArchitectureChanged'] });
+        expect(types(results)).toEqual(['Added', 'LearningRateChanged']);
+    });
+
+    test('minSeverity drops less severe types', () => {
+        expect(types(diffai.diff(old, changed, { minSeverity: 'warning' }))).toEqual([
+            'Added',
+            'LearningRateChanged',
+            'ModelArchitectureChanged',
+        ]);
+        expect(types(diffai.diff(old, changed, { minSeverity: diffai.Severity.Critical }))).toEqual([
+            'ModelArchitectureChanged',
+        ]);
+    });
+
+    test('severities override the defaults', () => {
+        const options = {
+            minSeverity: 'critical',
+            severities: { Modified: 'critical', ModelArchitectureChanged: 'info' },
+        };
+        expect(types(diffai.diff(old, changed, options))).toEqual(['Modified']);
+    });
+
+    test('skip files whose results would all be dropped', () => {
+        const dir = tempDir();
+        writeSafetensors(dir, 'a/model.safetensors', { w: [1, 2] });
+        writeSafetensors(dir, 'b/model.safetensors', { w: [1, 3] });
+        writeSafetensors(dir, 'b/extra.safetensors', { w: [1] });
+        const a = path.join(dir, 'a');
+        const b = path.join(dir, 'b');
+
+        expect(types(diffai.diffPaths(a, b))).toContain('Added');
+        expect(diffai.diffPaths(a, b, { excludeTypes: ['Added'] }).some((r) => r.path === 'extra.safetensors')).toBe(
+            false
+        );
+    });
+
+    test('reject unknown types and severities', () => {
+        expect(() => diffai.diff({}, {}, { onlyTypes: ['Renamed'] })).toThrow("Unknown diff type: 'Renamed'");
+        expect(() => diffai.diff({}, {}, { minSeverity: 'high' })).toThrow("Invalid severity: 'high'");
+        expect(() => diffai.diff({}, {}, { severities: { Added: 'low' } })).toThrow("Invalid severity: 'low'");
+    });
+});
@@ -0,0 +1,72 @@
+const fs = require('fs');
+const os = require('os');
+const path = require('path');
+
+// Build a minimal safetensors file from { name: Float32Array | number[] } tensors.
+function safetensorsBuffer(tensors) {
+    const header = {};
+    const chunks = [];
+    let offset = 0;
+    for (const [name, values] of Object.entries(tensors)) {
+        const data = Buffer.from(new Float32Array(values).buffer);
+        header[name] = {
+            dtype: 'F32',
+            shape: [values.length],
+            data_offsets: [offset, offset + data.length],
+        };
+        chunks.push(data);
+        offset += data.length;
+    }
+    let json = JSON.stringify(header);
+    json += ' '.repeat((8 - (json.length % 8)) % 8);
+    const len = Buffer.alloc(8);
+    len.writeBigUInt64LE(BigInt(json.length));
+    return Buffer.concat([len, Buffer.from(json), ...chunks]);
+}
+
+function tempDir() {
+    return fs.mkdtempSync(path.join(os.tmpdir(), 'diffai-js-'));
+}
+
+function writeSafetensors(dir, name, tensors) {
+    const file = path.join(dir, name);
+    fs.mkdirSync(path.dirname(file), { recursive: true });
+    fs.writeFileSync(file, safetensorsBuffer(tensors));
+    return file;
+}
+
+function tensorStats(mean, overrides = {}) {
+    return {
+        mean,
+        std: 0.5,
+        min: mean - 1,
+        max: mean + 1,
+        shape: [2, 3],
+        dtype: 'F32',
+        elementCount: 6,
+        ...overrides,
+    };
+}
+
+// One result of every diff type produced by the native converter.
+function allResultVariants() {
+    return [
+        { diffType: 'Added', path: 'a', newValue: 1 },
+        { diffType: 'Removed', path: 'b', value: 'gone' },
+        { diffType: 'Modified', path: 'c', oldValue: 1, newValue: 2 },
+        { diffType: 'TypeChanged', path: 'd', oldValue: 1, newValue: '1' },
+        { diffType: 'TensorShapeChanged', path: 'fc.weight', oldShape: [2, 3], newShape: [3, 3] },
+        { diffType: 'TensorStatsChanged', path: 'fc.bias', oldStats: tensorStats(0.1), newStats: tensorStats(0.4) },
+        { diffType: 'TensorDataChanged', path: 'conv.weight', oldMean: 0.1, newMean: 0.2 },
+        { diffType: 'ModelArchitectureChanged', path: 'arch', oldString: 'resnet18', newString: 'resnet50' },
+        { diffType: 'WeightSignificantChange', path: 'fc.weight', changeMagnitude: 0.75 },
+        { diffType: 'ActivationFunctionChanged', path: 'act', oldString: 'relu', newString: 'gelu' },
+        { diffType: 'LearningRateChanged', path: 'lr', oldFloat: 0.01, newFloat: 0.001 },
+        { diffType: 'OptimizerChanged', path: 'optimizer', oldString: 'sgd', newString: 'adam' },
+        { diffType: 'LossChange', path: 'loss', oldFloat: 0.5, newFloat: 0.3 },
+        { diffType: 'AccuracyChange', path: 'accuracy', oldFloat: 0.8, newFloat: 0.9 },
+        { diffType: 'ModelVersionChanged', path: 'version', oldString: '1.0', newString: '2.0' },
+    ];
+}
+
+module.exports = { safetensorsBuffer, tempDir, writeSafetensors, tensorStats, allResultVariants };
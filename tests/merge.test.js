@@ -0,0 +1,105 @@
+const diffai = require('../index.js');
+
+const base = {
+    lr: 0.01,
+    epochs: 10,
+    optimizer: { name: 'sgd', momentum: 0.9 },
+    layers: [
+        { name: 'conv1', channels: 32 },
+        { name: 'fc', units: 10 },
+    ],
+    tags: ['baseline'],
+};
+
+describe('merge3()', () => {
+    test('combines changes to different locations', () => {
+        const ours = { ...base, lr: 0.001 };
+        const theirs = { ...base, epochs: 20, seed: 1 };
+        const { merged, conflicts } = diffai.merge3(base, ours, theirs);
+
+        expect(conflicts).toEqual([]);
+        expect(merged).toEqual({ ...base, lr: 0.001, epochs: 20, seed: 1 });
+    });
+
+    test('accepts identical changes on both sides', () => {
+        const changed = { ...base, lr: 0.001, tags: ['baseline', 'v2'] };
+        const { merged, conflicts } = diffai.merge3(base, changed, { ...changed });
+
+        expect(conflicts).toEqual([]);
+        expect(merged).toEqual(changed);
+    });
+
+    test('reports conflicts with all three values and keeps the base value', () => {
+        const ours = { ...base, lr: 0.001, epochs: 12 };
+        const theirs = { ...base, lr: 0.1 };
+        const { merged, conflicts } = diffai.merge3(base, ours, theirs);
+
+        expect(conflicts).toEqual([{ path: 'lr', base: 0.01, ours: 0.001, theirs: 0.1 }]);
+        expect(merged).toEqual({ ...base, epochs: 12 });
+    });
+
+    test('leaves out absent values of a conflict', () => {
+        const { optimizer, ...withoutOptimizer } = base;
+        const ours = withoutOptimizer;
+        const theirs = { ...base, optimizer: { ...optimizer, momentum: 0.95 } };
+        const { merged, conflicts } = diffai.merge3(base, ours, theirs);
+
+        expect(conflicts).toEqual([{ path: 'optimizer', base: optimizer, theirs: theirs.optimizer }]);
+        expect(conflicts[0]).not.toHaveProperty('ours');
+        expect(merged.optimizer).toEqual(optimizer);
+    });
+
+    test('respects epsilon for numeric conflicts', () => {
+        const ours = { ...base, lr: 0.0010001 };
+        const theirs = { ...base, lr: 0.001 };
+
+        expect(diffai.merge3(base, ours, theirs).conflicts).toHaveLength(1);
+
+        const { merged, conflicts } = diffai.merge3(base, ours, theirs, { epsilon: 1e-6 });
+        expect(conflicts).toEqual([]);
+        expect(merged.lr).toBe(0.0010001);
+    });
+
+    test('matches layers by arrayIdKey', () => {
+        const ours = {
+            ...base,
+            layers: [
+                { name: 'conv1', channels: 64 },
+                { name: 'fc', units: 10 },
+            ],
+        };
+        const theirs = {
+            ...base,
+            layers: [
+                { name: 'fc', units: 100 },
+                { name: 'conv1', channels: 32 },
+                { name: 'dropout', p: 0.5 },
+            ],
+        };
+        const { merged, conflicts } = diffai.merge3(base, ours, theirs, { arrayIdKey: 'name' });
+
+        expect(conflicts).toEqual([]);
+        expect(merged.layers).toEqual([
+            { name: 'conv1', channels: 64 },
+            { name: 'fc', units: 100 },
+            { name: 'dropout', p: 0.5 },
+        ]);
+    });
+
+    test('reports an array as conflicting when both sides resize it differently', () => {
+        const ours = { ...base, tags: ['baseline', 'fast'] };
+        const theirs = { ...base, tags: [] };
+        const { merged, conflicts } = diffai.merge3(base, ours, theirs);
+
+        expect(conflicts).toEqual([{ path: 'tags', base: ['baseline'], ours: ['baseline', 'fast'], theirs: [] }]);
+        expect(merged.tags).toEqual(['baseline']);
+    });
+
+    test('reports a conflict between a removed value and a changed child', () => {
+        const ours = { ...base, optimizer: 'adam' };
+        const theirs = { ...base, optimizer: { name: 'sgd', momentum: 0.5 } };
+        const { conflicts } = diffai.merge3(base, ours, theirs);
+
+        expect(conflicts.map((c) => c.path)).toEqual(['optimizer']);
+    });
+});
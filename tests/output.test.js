@@ -0,0 +1,110 @@
+const fs = require('fs');
+const path = require('path');
+const diffai = require('../index.js');
+const { tempDir, writeSafetensors } = require('./helpers');
+
+function lines(text) {
+    return text.trimEnd().split('\n').map((line) => JSON.parse(line));
+}
+
+describe('ndjson output', () => {
+    const old = { a: 1, b: 'x', c: [1, 2] };
+    const newObj = { a: 2, b: 'x', d: true };
+
+    test('formatOutput writes one result per line', () => {
+        const results = diffai.diff(old, newObj);
+        const output = diffai.formatOutput(results, 'ndjson');
+
+        expect(output.endsWith('\n')).toBe(true);
+        expect(lines(output)).toEqual(results);
+    });
+
+    test('uses the field names of the result objects', () => {
+        const [result] = lines(diffai.formatOutput(diffai.diff({ a: 1 }, { a: 2 }), 'ndjson'));
+        expect(result).toEqual({ diffType: 'Modified', path: 'a', oldValue: 1, newValue: 2 });
+    });
+
+    test('keeps tensor statistics', async () => {
+        const dir = tempDir();
+        const a = writeSafetensors(dir, 'a.safetensors', { w: [1, 2, 3] });
+        const b = writeSafetensors(dir, 'b.safetensors', { w: [1, 2, 4] });
+        const results = diffai.diffPaths(a, b);
+
+        expect(results.length).toBeGreaterThan(0);
+        expect(lines(diffai.formatOutput(results, 'ndjson'))).toEqual(results);
+    });
+
+    test('is an empty string without results', () => {
+        expect(diffai.formatOutput([], 'ndjson')).toBe('');
+    });
+
+    test('can be the outputFormat of a Differ', () => {
+        const differ = new diffai.Differ({ outputFormat: 'ndjson' });
+        const results = differ.diff(old, newObj);
+        expect(differ.format(results)).toBe(diffai.formatOutput(results, 'ndjson'));
+    });
+});
+
+describe('NdjsonWriter', () => {
+    test('appends results to the file', () => {
+        const file = path.join(tempDir(), 'diff.ndjson');
+        const first = diffai.diff({ a: 1 }, { a: 2 });
+        const second = diffai.diff({ b: 1 }, { c: 1 });
+
+        const writer = new diffai.NdjsonWriter(file);
+        writer.write(first);
+        writer.write(second);
+        writer.close();
+
+        expect(lines(fs.readFileSync(file, 'utf8'))).toEqual([...first, ...second]);
+    });
+
+    test('keeps existing lines', () => {
+        const file = path.join(tempDir(), 'diff.ndjson');
+        const results = diffai.diff({ a: 1 }, { a: 2 });
+
+        for (let i = 0; i < 2; i++) {
+            const writer = new diffai.NdjsonWriter(file);
+            writer.write(results);
+            writer.close();
+        }
+
+        expect(lines(fs.readFileSync(file, 'utf8'))).toEqual([...results, ...results]);
+    });
+
+    test('writes the batches of a stream', async () => {
+        const dir = tempDir();
+        const a = writeSafetensors(dir, 'v1/a.safetensors', { w: [1, 2, 3] });
+        writeSafetensors(dir, 'v2/a.safetensors', { w: [1, 2, 4] });
+        writeSafetensors(dir, 'v2/b.safetensors', { w: [5] });
+        const file = path.join(dir, 'diff.ndjson');
+
+        const writer = new diffai.NdjsonWriter(file);
+        for await (const batch of diffai.diffPathsStream(path.dirname(a), path.join(dir, 'v2'), { batchSize: 1 })) {
+            writer.write(batch);
+        }
+        writer.close();
+
+        const expected = diffai.diffPaths(path.dirname(a), path.join(dir, 'v2'));
+        expect(lines(fs.readFileSync(file, 'utf8'))).toEqual(expected);
+    });
+
+    test('throws after close', () => {
+        const writer = new diffai.NdjsonWriter(path.join(tempDir(), 'diff.ndjson'));
+        writer.close();
+        expect(() => writer.write([])).toThrow('NdjsonWriter is closed');
+    });
+
+    test('reports a missing directory', () => {
+        const file = path.join(tempDir(), 'missing', 'diff.ndjson');
+        let error;
+        try {
+            new diffai.NdjsonWriter(file);
+        } catch (e) {
+            error = e;
+        }
+        expect(error.code).toBe('DIFFAI_FILE_NOT_FOUND');
+        expect(error.path).toBe(file);
+        expect(error.message).toContain('Cannot write');
+    });
+});
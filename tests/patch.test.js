@@ -0,0 +1,217 @@
+const diffai = require('../index.js');
+
+const oldConfig = {
+    lr: 0.01,
+    layers: [64, 32, 16, 8],
+    optimizer: { name: 'sgd', momentum: 0.9 },
+    paths: { 'data/train': 1, 'a~b': 2 },
+    dropout: 0.1,
+};
+const newConfig = {
+    lr: 0.001,
+    layers: [64, 48],
+    optimizer: { name: 'adam', betas: [0.9, 0.999] },
+    paths: { 'data/train': 2, 'a~b': 3 },
+    scheduler: null,
+};
+
+describe('toJsonPatch()', () => {
+    test('converts results to JSON Patch operations', () => {
+        expect(diffai.toJsonPatch(diffai.diff({ lr: 0.01, layers: [64] }, { lr: 0.001, layers: [64, 32] }))).toEqual([
+            { op: 'replace', path: '/lr', value: 0.001 },
+            { op: 'add', path: '/layers/1', value: 32 },
+        ]);
+    });
+
+    test('escapes JSON Pointer tokens', () => {
+        const patch = diffai.toJsonPatch(diffai.diff({ 'a/b': 1, 'c~': [1] }, { 'a/b': 2, 'c~': [2] }));
+        expect(patch.map((op) => op.path)).toEqual(['/a~1b', '/c~0/0']);
+    });
+
+    test('removes higher array indices first', () => {
+        const patch = diffai.toJsonPatch(diffai.diff([1, 2, 3, 4], [1]));
+        expect(patch).toEqual([
+            { op: 'remove', path: '/3' },
+            { op: 'remove', path: '/2' },
+            { op: 'remove', path: '/1' },
+        ]);
+    });
+
+    test('replaces the root value', () => {
+        expect(diffai.toJsonPatch(diffai.diff(1, 2))).toEqual([{ op: 'replace', path: '', value: 2 }]);
+    });
+
+    test('keeps null values', () => {
+        expect(diffai.toJsonPatch(diffai.diff({}, { a: null }))).toEqual([{ op: 'add', path: '/a', value: null }]);
+    });
+
+    test('skips results that are not edits', () => {
+        const patch = diffai.toJsonPatch([
+            { diffType: 'WeightSignificantChange', path: 'fc.weight', changeMagnitude: 0.75 },
+            { diffType: 'Modified', path: 'a', oldValue: 1, newValue: 2 },
+        ]);
+        expect(patch).toEqual([{ op: 'replace', path: '/a', value: 2 }]);
+    });
+
+    test('adds lower array indices first', () => {
+        const results = diffai.diff({ arr: [1] }, { arr: [1, 2, 3] });
+        expect(diffai.toJsonPatch([...results].reverse())).toEqual([
+            { op: 'add', path: '/arr/1', value: 2 },
+            { op: 'add', path: '/arr/2', value: 3 },
+        ]);
+    });
+
+    test('resolves keys containing dots against the old value', () => {
+        const old = { a: { 'x.y': 1 } };
+        const results = diffai.diff(old, { a: { 'x.y': 2, 'z.w': 3 } });
+        expect(diffai.toJsonPatch(results, old)).toEqual([
+            { op: 'replace', path: '/a/x.y', value: 2 },
+            { op: 'add', path: '/a/z.w', value: 3 },
+        ]);
+        expect(diffai.toJsonPatch(results).map((op) => op.path)).toEqual(['/a/x/y', '/a/z/w']);
+    });
+
+    test('rejects paths matched by arrayIdKey', () => {
+        const results = diffai.diff({ items: [{ id: 1, v: 1 }] }, { items: [{ id: 1, v: 2 }] }, { arrayIdKey: 'id' });
+        expect(() => diffai.toJsonPatch(results)).toThrow('have no index');
+    });
+});
+
+describe('applyPatch()', () => {
+    test('rebuilds the new value from diff results', () => {
+        const results = diffai.diff(oldConfig, newConfig);
+        expect(diffai.applyPatch(oldConfig, results)).toEqual(newConfig);
+    });
+
+    test('rebuilds the new value from a JSON Patch', () => {
+        const patch = diffai.toJsonPatch(diffai.diff(oldConfig, newConfig));
+        expect(diffai.applyPatch(oldConfig, JSON.parse(JSON.stringify(patch)))).toEqual(newConfig);
+    });
+
+    test('resolves keys containing dots', () => {
+        const old = { a: { 'x.y': 1 }, 'b.c': [1] };
+        const updated = { a: { 'x.y': 2 }, 'b.c': [1, 2, 3] };
+        expect(diffai.applyPatch(old, diffai.diff(old, updated))).toEqual(updated);
+    });
+
+    test('does not modify its argument', () => {
+        const old = { a: [1, 2] };
+        diffai.applyPatch(old, diffai.diff(old, { a: [1] }));
+        expect(old).toEqual({ a: [1, 2] });
+    });
+
+    test('supports all RFC 6902 operations', () => {
+        const patched = diffai.applyPatch({ a: { b: 1 }, list: [1, 3] }, [
+            { op: 'test', path: '/a/b', value: 1 },
+            { op: 'add', path: '/list/1', value: 2 },
+            { op: 'add', path: '/list/-', value: 4 },
+            { op: 'copy', from: '/a', path: '/c' },
+            { op: 'move', from: '/a/b', path: '/d' },
+            { op: 'replace', path: '/c/b', value: 5 },
+            { op: 'remove', path: '/a' },
+        ]);
+        expect(patched).toEqual({ list: [1, 2, 3, 4], c: { b: 5 }, d: 1 });
+    });
+
+    test('reports the failing operation', () => {
+        let error;
+        try {
+            diffai.applyPatch({ a: 1 }, [
+                { op: 'replace', path: '/a', value: 2 },
+                { op: 'test', path: '/a', value: 1 },
+            ]);
+        } catch (e) {
+            error = e;
+        }
+        expect(error.code).toBe('DIFFAI_INVALID_ARGUMENT');
+        expect(error.message).toBe("Cannot apply patch operation 1 (test '/a'): test failed");
+    });
+
+    test('rejects invalid pointers and indices', () => {
+        expect(() => diffai.applyPatch({}, [{ op: 'add', path: 'a', value: 1 }])).toThrow('invalid JSON Pointer');
+        expect(() => diffai.applyPatch([1], [{ op: 'add', path: '/01', value: 1 }])).toThrow('invalid array index');
+        expect(() => diffai.applyPatch([1], [{ op: 'remove', path: '/1' }])).toThrow('invalid array index');
+    });
+
+    test('rejects a mix of operations and results', () => {
+        expect(() =>
+            diffai.applyPatch({ a: 1 }, [
+                { op: 'remove', path: '/a' },
+                { diffType: 'Added', path: 'b', newValue: 1 },
+            ])
+        ).toThrow('cannot mix');
+    });
+});
+
+describe('patch() and unpatch()', () => {
+    const base = {
+        layers: [
+            { name: 'conv1', channels: 32 },
+            { name: 'fc', units: 10 },
+            { name: 'dropout', p: 0.1 },
+        ],
+        lr: 0.01,
+        warmup: [1, 2, 3],
+    };
+    const tuned = {
+        layers: [
+            { name: 'conv1', channels: 64 },
+            { name: 'fc', units: 10 },
+            { name: 'norm', eps: 1e-5 },
+        ],
+        lr: 0.001,
+        warmup: [1],
+        seed: 42,
+    };
+
+    test('patch rebuilds the new value', () => {
+        expect(diffai.patch(oldConfig, diffai.diff(oldConfig, newConfig))).toEqual(newConfig);
+    });
+
+    test('unpatch rebuilds the old value', () => {
+        expect(diffai.unpatch(newConfig, diffai.diff(oldConfig, newConfig))).toEqual(oldConfig);
+    });
+
+    test('find array elements by arrayIdKey', () => {
+        const results = diffai.diff(base, tuned, { arrayIdKey: 'name' });
+        expect(diffai.patch(base, results)).toEqual(tuned);
+
+        const reverted = diffai.unpatch(tuned, results);
+        expect(reverted.layers).toEqual([
+            { name: 'conv1', channels: 32 },
+            { name: 'fc', units: 10 },
+            { name: 'dropout', p: 0.1 },
+        ]);
+        expect(reverted).toEqual(base);
+    });
+
+    test('replays changes on a different value', () => {
+        const results = diffai.diff(base, tuned, { arrayIdKey: 'name' });
+        const other = { ...base, layers: [{ name: 'fc', units: 10 }, ...base.layers.filter((l) => l.name !== 'fc')], extra: true };
+        const patched = diffai.patch(other, results);
+
+        expect(patched.layers.find((l) => l.name === 'conv1').channels).toBe(64);
+        expect(patched.extra).toBe(true);
+    });
+
+    test('resolves object keys containing dots', () => {
+        const old = { 'model.v1': { depth: 1 } };
+        const results = diffai.diff(old, { 'model.v1': { depth: 2, width: 3 } });
+        expect(diffai.patch(old, results)).toEqual({ 'model.v1': { depth: 2, width: 3 } });
+    });
+
+    test('applies array insertions in any order', () => {
+        const results = diffai.diff({ arr: [1] }, { arr: [1, 2, 3, 4] });
+        expect(diffai.patch({ arr: [1] }, [...results].reverse())).toEqual({ arr: [1, 2, 3, 4] });
+        expect(diffai.unpatch({ arr: [1, 2, 3, 4] }, results)).toEqual({ arr: [1] });
+    });
+
+    test('reports results that do not fit the value', () => {
+        const results = diffai.diff({ a: { b: 1 } }, { a: { b: 2 } });
+        expect(() => diffai.patch({ c: 1 }, results)).toThrow("Cannot apply the Modified result at 'a.b': 'a.b' does not exist");
+        expect(() => diffai.unpatch({ a: 1 }, results)).toThrow('Cannot revert the Modified result');
+
+        const byId = diffai.diff({ l: [{ id: 1, v: 1 }] }, { l: [{ id: 1, v: 2 }] }, { arrayIdKey: 'id' });
+        expect(() => diffai.patch({ l: [{ id: 2, v: 1 }] }, byId)).toThrow('no element has id=1');
+    });
+});
@@ -0,0 +1,69 @@
+const diffai = require('../index.js');
+const { tensorStats } = require('./helpers');
+
+describe('rankChanges()', () => {
+    const stats = (path, oldStats, newStats) => ({
+        diffType: 'TensorStatsChanged',
+        path,
+        oldStats: tensorStats(oldStats.mean, oldStats),
+        newStats: tensorStats(newStats.mean, newStats),
+    });
+    const results = [
+        stats('a', { mean: 0, std: 1 }, { mean: 0.5, std: 1 }),
+        stats('b', { mean: 1, std: 1 }, { mean: 1.1, std: 4 }),
+        stats('c', { mean: 0, std: 2, min: -3, max: 3 }, { mean: 0, std: 0.5, min: -9, max: 1 }),
+        { diffType: 'TensorDataChanged', path: 'd', oldMean: 0.1, newMean: 1.1 },
+        { diffType: 'WeightSignificantChange', path: 'a', changeMagnitude: 0.75 },
+        { diffType: 'WeightSignificantChange', path: 'e', changeMagnitude: 0.25 },
+        { diffType: 'Modified', path: 'lr', oldValue: 0.1, newValue: 0.2 },
+    ];
+    const ranked = (options) => diffai.rankChanges(results, options).map(({ path, score }) => [path, score]);
+
+    test('rank by mean shift by default', () => {
+        const top = diffai.rankChanges(results, { k: 2 });
+        expect(top.map((change) => change.path)).toEqual(['d', 'a']);
+        expect(top[0].score).toBeCloseTo(1);
+        expect(top[1].result).toEqual(results[0]);
+    });
+
+    test('rank by each metric', () => {
+        expect(ranked({ by: 'stdRatio' })).toEqual([
+            ['b', 4],
+            ['c', 4],
+            ['a', 1],
+        ]);
+        expect(ranked({ by: 'changeMagnitude' })).toEqual([
+            ['a', 0.75],
+            ['e', 0.25],
+        ]);
+        expect(ranked({ by: 'maxAbs', k: 2 })).toEqual([
+            ['c', 6],
+            ['a', 0.5],
+        ]);
+
+        const [first, ...rest] = ranked({ by: 'relativeL2', k: 1 });
+        expect(rest).toEqual([]);
+        expect(first[0]).toBe('b');
+        expect(first[1]).toBeCloseTo(Math.sqrt(1.1 ** 2 + 16) / Math.sqrt(2) - 1);
+    });
+
+    test('rank by the actual L2 norms when both statistics have them', () => {
+        const withNorms = stats('f', { mean: 0, std: 1 }, { mean: 0, std: 1 });
+        withNorms.oldStats.l2Norm = 2;
+        withNorms.newStats.l2Norm = 8;
+        expect(diffai.rankChanges([withNorms, results[1]], { by: 'relativeL2' })[0]).toMatchObject({ path: 'f', score: 3 });
+
+        const diffed = diffai.diffTensors(new Float64Array([3, 4]), new Float64Array([6, 8]), { extendedStats: true });
+        expect(diffai.rankChanges(diffed, { by: 'relativeL2' })[0].score).toBeCloseTo(1);
+    });
+
+    test('break ties by path regardless of input order', () => {
+        const tied = ['z', 'm', 'a'].map((path) => ({ diffType: 'TensorDataChanged', path, oldMean: 0, newMean: 1 }));
+        expect(diffai.rankChanges(tied).map((change) => change.path)).toEqual(['a', 'm', 'z']);
+        expect(diffai.rankChanges(tied.reverse()).map((change) => change.path)).toEqual(['a', 'm', 'z']);
+    });
+
+    test('reject unknown metrics', () => {
+        expect(() => diffai.rankChanges(results, { by: 'variance' })).toThrow("Invalid ranking metric: 'variance'");
+    });
+});
@@ -0,0 +1,214 @@
+const diffai = require('../index.js');
+const { allResultVariants, tensorStats } = require('./helpers');
+
+describe('renderHtmlReport()', () => {
+    test('renders a standalone page', () => {
+        const html = diffai.renderHtmlReport(diffai.diff({ a: 1 }, { a: 2 }));
+
+        expect(html.startsWith('<!DOCTYPE html>')).toBe(true);
+        expect(html).toContain('<style>');
+        expect(html).not.toMatch(/<script|<link|src=/);
+        expect(html).toContain('<title>diffai report</title>');
+        expect(html.trimEnd().endsWith('</html>')).toBe(true);
+    });
+
+    test('renders every diff type with its color class', () => {
+        const html = diffai.renderHtmlReport(allResultVariants());
+
+        for (const result of allResultVariants()) {
+            expect(html).toContain(`<span class="badge">${result.diffType}</span>`);
+        }
+        expect(html).toContain('class="result added"');
+        expect(html).toContain('class="result removed"');
+        expect(html).toContain('class="result modified"');
+        expect(html).toContain('class="result tensor"');
+        expect(html).toContain('class="result model"');
+        expect(html).toContain('<p>15 differences</p>');
+    });
+
+    test('groups results into a collapsible path tree', () => {
+        const results = diffai.diff(
+            { model: { layers: [{ w: 1 }, { w: 2 }] } },
+            { model: { layers: [{ w: 3 }, { w: 4 }] } }
+        );
+        const html = diffai.renderHtmlReport(results);
+
+        expect(html).toContain('<details open><summary><code>model</code> <span class="count">2</span></summary>');
+        expect(html).toContain('<details><summary><code>layers</code> <span class="count">2</span></summary>');
+        expect(html).toContain('<summary><code>[1]</code> <span class="count">1</span></summary>');
+    });
+
+    test('shows tensor stats side by side with a labelled range chart', () => {
+        const html = diffai.renderHtmlReport([
+            { diffType: 'TensorStatsChanged', path: 'fc.bias', oldStats: tensorStats(0.1), newStats: tensorStats(0.4) },
+        ]);
+
+        expect(html).toContain('<tr><th>mean</th><td>0.1</td><td>0.4</td><td>0.3</td></tr>');
+        expect(html).toContain('<tr><th>shape</th><td>[2, 3]</td><td>[2, 3]</td><td></td></tr>');
+        expect(html).toMatch(/<svg class="range"[^>]*>/);
+        expect(html).toContain('<rect class="old"');
+        expect(html).toContain('<rect class="new"');
+        expect(html).toContain('<div class="caption">bar: mean ± std, tick: mean, line: min to max</div>');
+    });
+
+    test('draws histograms of the tensor values computed with extendedStats', () => {
+        const [result] = diffai.diffTensors(
+            Float64Array.from({ length: 100 }, (_, i) => i),
+            Float64Array.from({ length: 100 }, (_, i) => 2 * i),
+            { extendedStats: true }
+        );
+        expect(result.oldStats.histogram.low).toBe(0);
+        expect(result.oldStats.histogram.high).toBe(99);
+        expect(result.oldStats.histogram.counts).toHaveLength(32);
+        expect(result.oldStats.histogram.counts.reduce((sum, count) => sum + count)).toBe(100);
+
+        const html = diffai.renderHtmlReport([result]);
+        expect(html).toContain('<tr><th>p50</th>');
+        expect(html).toMatch(/<svg class="histogram"[^>]*>/);
+        expect(html).not.toContain('<svg class="range"');
+        // The old values span half of the shared axis from 0 to 198
+        expect(html).toContain('<rect class="old" x="32.00" y="');
+        expect(html).toContain('<text x="32" y="92">0</text><text x="320" y="92" text-anchor="end">198</text>');
+        expect(html).toContain('<div class="caption">finite values per bin, scaled to the largest bin</div>');
+
+        const histogram = { low: -1, high: 1, counts: [1, 0, 3] };
+        const given = diffai.renderHtmlReport([
+            {
+                diffType: 'TensorStatsChanged',
+                path: 'fc.bias',
+                oldStats: tensorStats(0, { histogram }),
+                newStats: tensorStats(0.5, { histogram: { ...histogram, high: 2 } }),
+            },
+        ]);
+        expect(given.match(/<rect class="old"/g)).toHaveLength(2);
+        // The last of the new bins spans 1 to 2 on the shared axis from -1 to 2
+        expect(given).toContain('<rect class="new" x="224.00" y="42.00" width="96.00" height="38.00">');
+    });
+
+    test('charts percentiles when the stats have them and nothing when they are not finite', () => {
+        const html = diffai.renderHtmlReport([
+            {
+                diffType: 'TensorStatsChanged',
+                path: 'fc.bias',
+                oldStats: tensorStats(0.1, { p1: -0.8, p50: 0.1, p99: 1 }),
+                newStats: tensorStats(0.4, { p1: -0.5, p50: 0.4, p99: 1.3 }),
+            },
+        ]);
+        expect(html).toContain('<div class="caption">bar: 1st to 99th percentile, tick: median, line: min to max</div>');
+
+        const nan = diffai.diffTensors(new Float32Array([1, 2, 3]), new Float32Array([1, NaN, 3]));
+        expect(diffai.renderHtmlReport(nan)).not.toContain('<svg');
+    });
+
+    test('escapes keys and values', () => {
+        const html = diffai.renderHtmlReport(diffai.diff({ '<b>': 'a' }, { '<b>': '</code><script>' }));
+
+        expect(html).not.toContain('<script>');
+        expect(html).toContain('&lt;b&gt;');
+        expect(html).toContain('&lt;/code&gt;&lt;script&gt;');
+    });
+
+    test('accepts a title and the number of expanded levels', () => {
+        const results = diffai.diff({ a: { b: 1 } }, { a: { b: 2 } });
+        const html = diffai.renderHtmlReport(results, { title: 'v1 & v2', expandDepth: 0 });
+
+        expect(html).toContain('<title>v1 &amp; v2</title>');
+        expect(html).not.toContain('<details open>');
+    });
+
+    test('is the html format of formatOutput', () => {
+        const results = allResultVariants();
+        expect(diffai.formatOutput(results, 'html')).toBe(diffai.renderHtmlReport(results));
+    });
+});
+
+describe('renderMarkdownReport()', () => {
+    test('starts with a count per diff type', () => {
+        const markdown = diffai.renderMarkdownReport(allResultVariants());
+
+        expect(markdown.startsWith('## diffai report\n')).toBe(true);
+        expect(markdown).toContain('**15 differences**');
+        expect(markdown).toContain('| Diff type | Count |\n| --- | ---: |\n| Added | 1 |\n');
+        expect(markdown).toContain('| ModelVersionChanged | 1 |');
+    });
+
+    test('has a collapsed section per layer prefix', () => {
+        const markdown = diffai.renderMarkdownReport(allResultVariants());
+
+        expect(markdown).toContain('<details>\n<summary><code>fc</code> (3)</summary>');
+        expect(markdown).toContain('<summary><code>conv</code> (1)</summary>');
+        expect(markdown).toContain('<summary><code>(root)</code> (11)</summary>');
+        expect(markdown).toContain('| <code>c</code> | Modified | <code>1</code> | <code>2</code> |');
+        expect(markdown).toContain('| <code>fc.weight</code> | TensorShapeChanged | <code>[2, 3]</code> | <code>[3, 3]</code> |');
+    });
+
+    test('groups by a fixed number of path segments', () => {
+        const results = diffai.diff(
+            { encoder: { layer1: { w: 1 }, layer2: { w: 1 } } },
+            { encoder: { layer1: { w: 2 }, layer2: { w: 2 } } }
+        );
+
+        expect(diffai.renderMarkdownReport(results)).toContain('<summary><code>encoder.layer2</code> (1)</summary>');
+        expect(diffai.renderMarkdownReport(results, { groupDepth: 1 })).toContain(
+            '<summary><code>encoder</code> (2)</summary>'
+        );
+    });
+
+    test('keeps values on one line inside the table', () => {
+        const markdown = diffai.renderMarkdownReport([
+            { diffType: 'Modified', path: 'note', oldValue: 'a|b', newValue: '<br>' },
+        ]);
+
+        expect(markdown).toContain('| <code>note</code> | Modified | <code>&quot;a&#124;b&quot;</code> | <code>&quot;&lt;br&gt;&quot;</code> |');
+    });
+
+    test('truncates long values and sections', () => {
+        const results = [];
+        for (let i = 0; i < 10; i++) {
+            results.push({ diffType: 'Added', path: `layer.w${i}`, newValue: 'x'.repeat(100) });
+        }
+        const markdown = diffai.renderMarkdownReport(results, { maxValueLength: 10, maxRowsPerGroup: 3 });
+
+        expect(markdown).toContain(`<code>&quot;${'x'.repeat(8)}…</code>`);
+        expect(markdown).not.toContain('layer.w3');
+        expect(markdown).toContain('| _7 more_ | | | |');
+    });
+
+    test('stays under maxLength', () => {
+        const results = [];
+        for (let i = 0; i < 1000; i++) {
+            results.push({ diffType: 'Modified', path: `layer${i}.weight`, oldValue: i, newValue: i + 1 });
+        }
+        const markdown = diffai.renderMarkdownReport(results, { maxLength: 5000 });
+
+        expect(markdown.length).toBeLessThanOrEqual(5000);
+        expect(markdown).toContain('**1000 differences**');
+        expect(markdown).toMatch(/_Report truncated: \d+ more differences in \d+ more groups are not shown._/);
+    });
+
+    test('shows as many rows of the first group as fit under maxLength', () => {
+        const results = [];
+        for (let i = 0; i < 100; i++) {
+            results.push({ diffType: 'Modified', path: `layer.w${i}`, oldValue: i, newValue: i + 1 });
+        }
+        results.push({ diffType: 'Modified', path: 'head.w', oldValue: 0, newValue: 1 });
+        const markdown = diffai.renderMarkdownReport(results, { maxLength: 2000, maxRowsPerGroup: 100 });
+
+        expect(markdown.length).toBeLessThanOrEqual(2000);
+        expect(markdown).toContain('<summary><code>layer</code> (100)</summary>');
+        expect(markdown).toContain('| <code>layer.w0</code> | Modified | <code>0</code> | <code>1</code> |');
+        expect(markdown).toMatch(/\| _\d+ more_ \| \| \| \|/);
+        expect(markdown).toContain('_Report truncated: 1 more differences in 1 more groups are not shown._');
+    });
+
+    test('escapes the title', () => {
+        const markdown = diffai.renderMarkdownReport([], { title: 'a | b #1\n<i>' });
+
+        expect(markdown.startsWith('## a &#124; b &#35;1 &lt;i&gt;\n')).toBe(true);
+    });
+
+    test('is the markdown format of formatOutput', () => {
+        const results = allResultVariants();
+        expect(diffai.formatOutput(results, 'markdown')).toBe(diffai.renderMarkdownReport(results));
+    });
+});
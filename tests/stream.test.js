@@ -0,0 +1,126 @@
+const fs = require('fs');
+const path = require('path');
+const diffai = require('../index.js');
+const { tempDir, writeSafetensors } = require('./helpers');
+
+async function collect(stream) {
+    const batches = [];
+    for await (const batch of stream) {
+        batches.push(batch);
+    }
+    return batches;
+}
+
+function modelDirs() {
+    const dir = tempDir();
+    for (let i = 0; i < 5; i++) {
+        writeSafetensors(dir, `old/layer${i}.safetensors`, { w: [1, 2, 3], b: [i] });
+        writeSafetensors(dir, `new/layer${i}.safetensors`, { w: [1, 2, 4], b: [i + 1] });
+    }
+    writeSafetensors(dir, 'new/extra.safetensors', { w: [5] });
+    return { old: path.join(dir, 'old'), new: path.join(dir, 'new') };
+}
+
+describe('streaming API', () => {
+    test('diffStream yields the results of diff in batches', async () => {
+        const old = {};
+        const newObj = {};
+        for (let i = 0; i < 25; i++) {
+            old[`k${i}`] = i;
+            newObj[`k${i}`] = i + 1;
+        }
+
+        const batches = await collect(diffai.diffStream(old, newObj, { batchSize: 10 }));
+        expect(batches.map((b) => b.length)).toEqual([10, 10, 5]);
+        expect(batches.flat()).toEqual(diffai.diff(old, newObj));
+    });
+
+    test('diffPathsStream yields the results of diffPaths', async () => {
+        const dirs = modelDirs();
+        const batches = await collect(diffai.diffPathsStream(dirs.old, dirs.new, { batchSize: 3 }));
+        expect(batches.length).toBeGreaterThan(1);
+        batches.forEach((batch) => expect(batch.length).toBeLessThanOrEqual(3));
+        expect(batches.flat()).toEqual(diffai.diffPaths(dirs.old, dirs.new));
+    });
+
+    test('finishes without batches when nothing changed', async () => {
+        expect(await collect(diffai.diffStream({ a: 1 }, { a: 1 }))).toEqual([]);
+    });
+
+    test('stops when the loop exits early', async () => {
+        const dirs = modelDirs();
+        const stream = diffai.diffPathsStream(dirs.old, dirs.new, { batchSize: 1 });
+        for await (const batch of stream) {
+            expect(batch.length).toBe(1);
+            break;
+        }
+        expect(await stream.next()).toEqual({ done: true });
+    });
+
+    test('stops the producer of a stream dropped without return()', async () => {
+        if (!fs.existsSync('/proc/self/status')) {
+            return;
+        }
+        require('v8').setFlagsFromString('--expose-gc');
+        const gc = require('vm').runInNewContext('gc');
+        const threads = () => Number(/Threads:\s+(\d+)/.exec(fs.readFileSync('/proc/self/status', 'utf8'))[1]);
+        const settle = () => new Promise((resolve) => setTimeout(resolve, 20));
+
+        const dirs = modelDirs();
+        const before = threads();
+        await (async () => {
+            const streams = Array.from({ length: 4 }, () => diffai.diffPathsStream(dirs.old, dirs.new, { batchSize: 1 }));
+            for (const stream of streams) {
+                expect((await stream.next()).done).toBe(false);
+            }
+            // The producers are blocked on a full queue
+            await settle();
+            expect(threads()).toBeGreaterThanOrEqual(before + 4);
+        })();
+
+        for (let attempt = 0; attempt < 100 && threads() > before; attempt++) {
+            gc();
+            await settle();
+        }
+        expect(threads()).toBeLessThanOrEqual(before);
+    });
+
+    test('settles a pending next() when the stream is closed', async () => {
+        const dirs = modelDirs();
+        const stream = diffai.diffPathsStream(dirs.old, dirs.new, { batchSize: 1 });
+        const pending = stream.next();
+        expect(await stream.return()).toEqual({ done: true });
+        await pending;
+        expect(await stream.next()).toEqual({ done: true });
+    });
+
+    test('more streams than threadpool workers can wait at once', async () => {
+        const dirs = modelDirs();
+        const streams = Array.from({ length: 8 }, () => diffai.diffPathsStream(dirs.old, dirs.new, { batchSize: 1 }));
+        const pending = streams.map((stream) => stream.next());
+
+        expect(await diffai.diffAsync({ a: 1 }, { a: 2 })).toHaveLength(1);
+        for (const batch of await Promise.all(pending)) {
+            expect(batch.value).toHaveLength(1);
+        }
+        await Promise.all(streams.map((stream) => stream.return()));
+    });
+
+    test('rejects with DIFFAI_CANCELLED when the signal is aborted', async () => {
+        const dirs = modelDirs();
+        const controller = new AbortController();
+        controller.abort();
+        await expect(collect(diffai.diffPathsStream(dirs.old, dirs.new, {}, controller.signal)))
+            .rejects.toMatchObject({ code: 'DIFFAI_CANCELLED' });
+    });
+
+    test('rejects with structured errors', async () => {
+        const missing = path.join(tempDir(), 'missing.safetensors');
+        await expect(collect(diffai.diffPathsStream(missing, missing)))
+            .rejects.toMatchObject({ code: 'DIFFAI_FILE_NOT_FOUND', path: missing });
+    });
+
+    test('rejects an empty batch size', () => {
+        expect(() => diffai.diffStream({}, {}, { batchSize: 0 })).toThrow('Invalid batch size');
+    });
+});
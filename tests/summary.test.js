@@ -0,0 +1,54 @@
+const diffai = require('../index.js');
+const { tensorStats, allResultVariants } = require('./helpers');
+
+describe('summarize()', () => {
+    const stats = (path, oldMean, newMean, elementCount = 6) => ({
+        diffType: 'TensorStatsChanged',
+        path,
+        oldStats: tensorStats(oldMean, { elementCount }),
+        newStats: tensorStats(newMean, { elementCount }),
+    });
+    const results = [
+        stats('model.layers.0.attn.q', 0.1, 0.3, 16),
+        stats('model.layers.0.attn.k', 0.2, 0.1, 16),
+        { diffType: 'TensorDataChanged', path: 'model.layers.0.mlp.w', oldMean: 0.5, newMean: 1.1 },
+        stats('model.layers.1.attn.q', 0, -0.05, 8),
+        { diffType: 'Added', path: 'model.layers.1.attn.bias', newValue: 0 },
+        { diffType: 'Modified', path: 'lr', oldValue: 0.1, newValue: 0.2 },
+    ];
+
+    test('aggregate results per layer prefix', () => {
+        const [first, second, root] = diffai.summarize(results, { groupDepth: 3 });
+
+        expect(first.prefix).toBe('model.layers.0');
+        expect(first.total).toBe(3);
+        expect(first.counts).toEqual({ TensorStatsChanged: 2, TensorDataChanged: 1 });
+        expect(first.parameters).toBe(32);
+        expect(first.meanAbsMeanShift).toBeCloseTo(0.3);
+        expect(first.maxAbsMeanShift).toBeCloseTo(0.6);
+
+        expect(second.prefix).toBe('model.layers.1');
+        expect(second.counts).toEqual({ TensorStatsChanged: 1, Added: 1 });
+        expect(second.parameters).toBe(8);
+        expect(second.maxAbsMeanShift).toBeCloseTo(0.05);
+
+        expect(root).toEqual({ prefix: 'lr', total: 1, counts: { Modified: 1 }, parameters: 0 });
+    });
+
+    test('group by parent path by default', () => {
+        expect(diffai.summarize(results).map((group) => group.prefix)).toEqual([
+            'model.layers.0.attn',
+            'model.layers.0.mlp',
+            'model.layers.1.attn',
+            '',
+        ]);
+    });
+
+    test('accept every result type', () => {
+        const groups = diffai.summarize(allResultVariants(), { groupDepth: 0 });
+        expect(groups).toHaveLength(1);
+        expect(groups[0].total).toBe(15);
+        expect(Object.keys(groups[0].counts).sort()).toEqual(allResultVariants().map((r) => r.diffType).sort());
+        expect(diffai.summarize([])).toEqual([]);
+    });
+});
@@ -0,0 +1,165 @@
+const diffai = require('../index.js');
+const { tempDir, writeSafetensors } = require('./helpers');
+
+describe('diffTensors()', () => {
+    test('returns no results for identical tensors', () => {
+        const weights = new Float32Array([0.1, 0.2, 0.3, 0.4]);
+        expect(diffai.diffTensors(weights, weights.slice())).toEqual([]);
+    });
+
+    test('reports shape changes', () => {
+        const results = diffai.diffTensors(
+            new Float32Array(6),
+            new Float32Array(6),
+            { oldShape: [2, 3], newShape: [3, 2], path: 'fc.weight' }
+        );
+        expect(results).toHaveLength(1);
+        expect(results[0]).toMatchObject({
+            diffType: 'TensorShapeChanged',
+            path: 'fc.weight',
+            oldShape: [2, 3],
+            newShape: [3, 2],
+        });
+    });
+
+    test('reports significant statistic changes with tensor stats', () => {
+        const results = diffai.diffTensors(
+            new Float64Array([1, 2, 3, 4]),
+            new Float64Array([2, 4, 6, 8]),
+            { shape: [2, 2], dtype: 'float64' }
+        );
+        expect(results).toHaveLength(1);
+        const [result] = results;
+        expect(result.diffType).toBe('TensorStatsChanged');
+        expect(result.path).toBe('tensor');
+        expect(result.oldStats).toMatchObject({ mean: 2.5, min: 1, max: 4, shape: [2, 2], dtype: 'float64', elementCount: 4 });
+        expect(result.newStats).toMatchObject({ mean: 5, min: 2, max: 8 });
+    });
+
+    test('reports small changes as TensorDataChanged', () => {
+        const oldData = Float32Array.from({ length: 1000 }, (_, i) => i % 10);
+        const newData = oldData.slice();
+        newData[0] = 0.5;
+        const [result] = diffai.diffTensors(oldData, newData);
+        expect(result.diffType).toBe('TensorDataChanged');
+        expect(result.oldMean).toBeCloseTo(4.5, 6);
+        expect(result.newMean).toBeGreaterThan(4.5);
+    });
+
+    test('honours epsilon', () => {
+        const results = diffai.diffTensors(
+            new Float32Array([1, 2]),
+            new Float32Array([1.0001, 2]),
+            { epsilon: 0.001 }
+        );
+        expect(results).toEqual([]);
+    });
+
+    test('supports integer and BigInt64 arrays', () => {
+        expect(diffai.diffTensors(new Int8Array([1, -2]), new Int8Array([1, -3]))).toHaveLength(1);
+        expect(diffai.diffTensors(new Uint8Array([1, 2]), new Uint8Array([1, 2]))).toEqual([]);
+        const [result] = diffai.diffTensors(new BigInt64Array([1n, 2n]), new BigInt64Array([10n, 20n]));
+        expect(result.diffType).toBe('TensorStatsChanged');
+        expect(result.oldStats.dtype).toBe('int64');
+    });
+
+    test('rejects shapes that do not match the data length', () => {
+        expect(() => diffai.diffTensors(new Float32Array(4), new Float32Array(4), { shape: [3] }))
+            .toThrow('Shape');
+    });
+
+    test('rejects unsupported TypedArray types', () => {
+        expect(() => diffai.diffTensors(new Int16Array(2), new Int16Array(2)))
+            .toThrow('Unsupported TypedArray type');
+    });
+
+    test('reports extended statistics with extendedStats', () => {
+        const oldData = new Float64Array([0, 0, 1, 2, 3, 4, 5, 6, 7, 8]);
+        const newData = new Float64Array([0, -2, 4, 6, 8, 10, 12, 14, 16, 18]);
+        expect(diffai.diffTensors(oldData, newData)[0].oldStats.l1Norm).toBeUndefined();
+
+        const [result] = diffai.diffTensors(oldData, newData, { extendedStats: true });
+        expect(result.oldStats).toMatchObject({ l1Norm: 36, p1: 0, p50: 3.5, zeroFraction: 0.2, nanCount: 0, infCount: 0 });
+        expect(result.oldStats.l2Norm).toBeCloseTo(Math.sqrt(204));
+        expect(result.oldStats.p99).toBeCloseTo(7.91);
+        expect(result.newStats).toMatchObject({ l1Norm: 90, zeroFraction: 0.1 });
+        expect(result.newStats.p1).toBeCloseTo(-1.82);
+    });
+
+    test('reports tensors that gain NaN or infinite values', () => {
+        const nan = diffai.diffTensors(new Float32Array([1, 2, 3]), new Float32Array([1, NaN, 3]), { path: 'fc.weight' });
+        expect(nan).toHaveLength(1);
+        expect(nan[0].diffType).toBe(diffai.DiffType.TensorNanIntroduced);
+        expect(nan[0].oldStats.nanCount).toBe(0);
+        expect(nan[0].newStats.nanCount).toBe(1);
+        expect(nan[0].newStats.l1Norm).toBeUndefined();
+        const [extended] = diffai.diffTensors(new Float32Array([1, 2, 3]), new Float32Array([1, NaN, 3]), { extendedStats: true });
+        expect(extended.newStats).toMatchObject({ nanCount: 1, l1Norm: 4, p50: 2 });
+
+        const [inf] = diffai.diffTensors(new Float32Array([1, 2, 3]), new Float32Array([1, Infinity, 3]));
+        expect(inf.diffType).toBe('TensorInfIntroduced');
+        expect(inf.newStats.infCount).toBe(1);
+
+        const [removed] = diffai.diffTensors(new Float32Array([1, NaN, 3]), new Float32Array([1, 2, 3]));
+        expect(removed.diffType).not.toBe('TensorNanIntroduced');
+
+        const [json] = JSON.parse(diffai.formatOutput(nan, 'json'));
+        expect(json.TensorNanIntroduced[2].nan_count).toBe(1);
+        expect(diffai.formatOutput(nan, 'diffai')).toContain('fc.weight gained NaN values: 0 -> 1');
+    });
+
+    test('keeps NaN results and their counts through the other functions', () => {
+        const nan = diffai.diffTensors(new Float32Array([1, 2, 3]), new Float32Array([1, NaN, 3]), { path: 'fc.weight' });
+
+        const [line] = diffai.formatOutput(nan, 'ndjson').trim().split('\n').map((l) => JSON.parse(l));
+        expect(line).toMatchObject({ diffType: 'TensorNanIntroduced', newStats: { nanCount: 1 } });
+
+        const [inverted] = diffai.invert(nan);
+        expect(inverted.diffType).toBe('TensorStatsChanged');
+        expect(inverted.oldStats.nanCount).toBe(1);
+        expect(diffai.invert([inverted])[0].diffType).toBe('TensorNanIntroduced');
+
+        expect(diffai.compose(nan, [])[0].diffType).toBe('TensorNanIntroduced');
+        expect(diffai.summarize(nan)[0].counts).toEqual({ TensorNanIntroduced: 1 });
+        expect(diffai.rankChanges(nan, { by: 'maxAbs' })[0].result.diffType).toBe('TensorNanIntroduced');
+        expect(diffai.renderMarkdownReport(nan)).toContain('TensorNanIntroduced');
+        expect(diffai.renderHtmlReport(nan)).toContain('gained NaN values: 0 -&gt; 1');
+    });
+
+    test('reports safetensors tensors that gain NaN values', () => {
+        const dir = tempDir();
+        const a = writeSafetensors(dir, 'a.safetensors', { w: [0, 1, 2, 3], b: [1, 2] });
+        const b = writeSafetensors(dir, 'b.safetensors', { w: [0, 1, NaN, 0], b: [1, 2] });
+
+        const results = diffai.diffPaths(a, b);
+        const nan = results.filter((r) => r.diffType === 'TensorNanIntroduced');
+        expect(nan).toHaveLength(1);
+        expect(nan[0].path).toBe('tensors.w');
+        expect([nan[0].oldStats.nanCount, nan[0].newStats.nanCount]).toEqual([0, 1]);
+        expect(results.some((r) => r.path.endsWith('nan_count'))).toBe(false);
+
+        const critical = diffai.diffPaths(a, b, { minSeverity: 'critical' });
+        expect(critical.map((r) => r.diffType)).toContain('TensorNanIntroduced');
+        const demoted = diffai.diffPaths(a, b, {
+            minSeverity: 'warning',
+            severities: { TensorNanIntroduced: 'info' },
+        });
+        expect(demoted.some((r) => r.diffType === 'TensorNanIntroduced')).toBe(false);
+    });
+
+    test('adds extended statistics to safetensors data summaries with extendedStats', () => {
+        const dir = tempDir();
+        const a = writeSafetensors(dir, 'a.safetensors', { w: [0, 1, 2, 3] });
+        const b = writeSafetensors(dir, 'b.safetensors', { w: [0, 1, NaN, 0] });
+
+        const changed = Object.fromEntries(
+            diffai
+                .diffPaths(a, b, { extendedStats: true })
+                .filter((r) => r.diffType === 'Modified')
+                .map((r) => [r.path.replace('tensors.w.data_summary.', ''), [r.oldValue, r.newValue]])
+        );
+        expect(changed.nan_count).toEqual([0, 1]);
+        expect(changed.zero_fraction).toEqual([0.25, 0.5]);
+        expect(changed.l1_norm).toEqual([6, 1]);
+    });
+});
@@ -0,0 +1,154 @@
+const diffai = require('../index.js');
+const { tempDir, writeSafetensors } = require('./helpers');
+
+const paths = (results) => results.map((r) => r.path).sort();
+
+describe('epsilonRules', () => {
+    const old = { fc: { bias: 0.1, weight: 0.2 }, ln: { gain: 1 } };
+    const changed = { fc: { bias: 0.1005, weight: 0.2005 }, ln: { gain: 1.0000001 } };
+
+    test('apply per-path tolerances to plain values', () => {
+        const results = diffai.diff(old, changed, {
+            epsilonRules: [
+                { pattern: '**.bias', epsilon: 1e-3 },
+                { pattern: 'ln.*', epsilon: 1e-9 },
+            ],
+        });
+        expect(paths(results)).toEqual(['fc.weight', 'ln.gain']);
+    });
+
+    test('fall back to epsilon for paths no rule matches', () => {
+        const results = diffai.diff(old, changed, {
+            epsilon: 1e-2,
+            epsilonRules: [{ pattern: 'ln.gain', epsilon: 1e-9 }],
+        });
+        expect(paths(results)).toEqual(['ln.gain']);
+    });
+
+    test('treat rules with only an epsilon as absolute under another default mode', () => {
+        const results = diffai.diff(old, changed, {
+            toleranceMode: 'relative',
+            rtol: 1e-9,
+            epsilonRules: [{ pattern: '**.bias', epsilon: 1e-3 }],
+        });
+        expect(paths(results)).toEqual(['fc.weight', 'ln.gain']);
+
+        const relative = diffai.diff(old, changed, {
+            toleranceMode: 'relative',
+            rtol: 1e-9,
+            epsilonRules: [{ pattern: '**.bias', epsilon: 1e-3, toleranceMode: 'relative' }],
+        });
+        expect(paths(relative)).toEqual(['fc.bias', 'fc.weight', 'ln.gain']);
+    });
+
+    test('let the most specific rule win regardless of order', () => {
+        const epsilonRules = [
+            { pattern: 'fc.weight', epsilon: 1e-6 },
+            { pattern: 'fc.*', epsilon: 1e-2 },
+        ];
+        expect(paths(diffai.diff(old, changed, { epsilonRules }))).toEqual(['fc.weight', 'ln.gain']);
+        expect(paths(diffai.diff(old, changed, { epsilonRules: [...epsilonRules].reverse() }))).toEqual([
+            'fc.weight',
+            'ln.gain',
+        ]);
+    });
+
+    test('accept regular expressions', () => {
+        const results = diffai.diff(old, changed, {
+            epsilonRules: [{ pattern: /^(fc|ln)\./, epsilon: 1e-3 }, { pattern: /WEIGHT$/i, epsilon: 1e-9 }],
+        });
+        expect(paths(results)).toEqual(['fc.weight']);
+    });
+
+    test('apply to tensor statistics', () => {
+        const tensor = (data) => ({ shape: [3], dtype: 'float32', data });
+        const results = diffai.diff(
+            { bias: tensor([1, 2, 3]), norm: tensor([1, 1, 1]) },
+            { bias: tensor([1.0005, 2, 3]), norm: tensor([1, 1, 1.00001]) },
+            { epsilonRules: [{ pattern: 'bias', epsilon: 1e-3 }, { pattern: 'norm', epsilon: 1e-7 }] }
+        );
+        expect(results.filter((r) => r.diffType.startsWith('Tensor')).map((r) => r.path)).toEqual(['norm']);
+    });
+
+    test('cover the statistics of matching safetensors tensors', () => {
+        const dir = tempDir();
+        const a = writeSafetensors(dir, 'a.safetensors', { 'fc.bias': [1, 2, 3], 'ln.weight': [1, 1, 1] });
+        const b = writeSafetensors(dir, 'b.safetensors', { 'fc.bias': [1.0005, 2, 3], 'ln.weight': [1, 1, 1.00001] });
+        const epsilonRules = [
+            { pattern: '**.bias', epsilon: 1e-3 },
+            { pattern: '**.weight', epsilon: 1e-7 },
+        ];
+
+        const results = new diffai.Differ({ epsilonRules }).diffPaths(a, b);
+        expect(results.length).toBeGreaterThan(0);
+        expect(results.every((r) => r.path.startsWith('tensors.ln.weight.'))).toBe(true);
+    });
+
+    test('reject invalid patterns', () => {
+        let error;
+        try {
+            diffai.diff({}, {}, { epsilonRules: [{ pattern: /(?<=a)b/, epsilon: 1 }] });
+        } catch (e) {
+            error = e;
+        }
+        expect(error.code).toBe('DIFFAI_INVALID_OPTION');
+        expect(error.message).toContain('Invalid path pattern');
+        expect(() => diffai.diff({}, {}, { epsilonRules: [{ pattern: 5, epsilon: 1 }] })).toThrow(
+            'Expected a glob string or a RegExp'
+        );
+    });
+});
+
+describe('toleranceMode', () => {
+    const old = { small: 1e-6, big: 1000 };
+    const changed = { small: 1.05e-6, big: 1000.5 };
+
+    test('defaults to an absolute epsilon', () => {
+        expect(paths(diffai.diff(old, changed, { epsilon: 1e-3 }))).toEqual(['big']);
+        expect(paths(diffai.diff(old, changed, { toleranceMode: 'absolute', epsilon: 1 }))).toEqual([]);
+    });
+
+    test('relative scales with the larger magnitude', () => {
+        expect(paths(diffai.diff(old, changed, { toleranceMode: 'relative', rtol: 1e-3 }))).toEqual(['small']);
+        expect(paths(diffai.diff(old, changed, { toleranceMode: 'relative', rtol: 0.1 }))).toEqual([]);
+    });
+
+    test('numpy combines atol and rtol with numpy defaults', () => {
+        expect(paths(diffai.diff(old, changed, { toleranceMode: 'numpy' }))).toEqual(['big', 'small']);
+        expect(paths(diffai.diff(old, changed, { toleranceMode: 'numpy', rtol: 1e-3 }))).toEqual(['small']);
+        expect(paths(diffai.diff(old, changed, { toleranceMode: 'numpy', rtol: 1e-3, epsilon: 1e-7 }))).toEqual([]);
+    });
+
+    test('ulp counts representable values in between', () => {
+        expect(diffai.diff({ x: 0.1 + 0.2 }, { x: 0.3 }, { toleranceMode: 'ulp', maxUlps: 1 })).toEqual([]);
+        expect(diffai.diff({ x: -0 }, { x: 0 }, { toleranceMode: 'ulp', maxUlps: 0 })).toEqual([]);
+        expect(paths(diffai.diff(old, changed, { toleranceMode: 'ulp', maxUlps: 1000 }))).toEqual(['big', 'small']);
+    });
+
+    test('applies to tensor statistics and tensor elements', () => {
+        const tensor = (data) => ({ shape: [2], dtype: 'float32', data });
+        const results = diffai.diff({ w: tensor([1000, 2000]) }, { w: tensor([1000.5, 2001]) }, {
+            toleranceMode: 'relative',
+            rtol: 1e-3,
+        });
+        expect(results).toEqual([]);
+
+        const options = { toleranceMode: 'relative', rtol: 0.1 };
+        expect(diffai.diffTensors(new Float32Array([1000, 1e-6]), new Float32Array([1000.5, 1.05e-6]), options)).toEqual([]);
+    });
+
+    test('can be set per rule', () => {
+        const results = diffai.diff(old, changed, {
+            epsilon: 1e-3,
+            epsilonRules: [{ pattern: 'small', toleranceMode: 'relative', rtol: 0.1 }],
+        });
+        expect(paths(results)).toEqual(['big']);
+    });
+
+    test('rejects incomplete or invalid settings', () => {
+        expect(() => diffai.diff({}, {}, { toleranceMode: 'relative' })).toThrow('requires rtol');
+        expect(() => diffai.diff({}, {}, { toleranceMode: 'ulp' })).toThrow('requires maxUlps');
+        expect(() => diffai.diff({}, {}, { toleranceMode: 'fuzzy' })).toThrow('Invalid tolerance mode');
+        expect(() => diffai.diff({}, {}, { rtol: -1 })).toThrow('must not be negative');
+    });
+});
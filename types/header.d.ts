@@ -0,0 +1,153 @@
+/* tslint:disable */
+/* eslint-disable */
+
+/* auto-generated by NAPI-RS */
+
+/** Shape of the errors thrown (or used to reject Promises) by diffai-js. */
+export interface DiffaiError extends Error {
+  code: ErrorCode
+  /** File or directory the error concerns, when known */
+  path?: string
+  /** Underlying error reported by the parser or the operating system */
+  cause?: Error
+}
+
+/** `DiffStream` is an async iterator, so it can be used with `for await`. */
+export interface DiffStream extends AsyncIterableIterator<Array<DiffResult>> {}
+
+/** A difference found by `diff`, `diffPaths` and friends, discriminated on `diffType`. */
+export type DiffResult =
+  | AddedResult
+  | RemovedResult
+  | ModifiedResult
+  | TypeChangedResult
+  | TensorShapeChangedResult
+  | TensorStatsChangedResult
+  | TensorDataChangedResult
+  | ModelArchitectureChangedResult
+  | WeightSignificantChangeResult
+  | ActivationFunctionChangedResult
+  | LearningRateChangedResult
+  | OptimizerChangedResult
+  | LossChangeResult
+  | AccuracyChangeResult
+  | ModelVersionChangedResult
+  | TensorNanIntroducedResult
+  | TensorInfIntroducedResult
+
+export interface AddedResult {
+  diffType: DiffType.Added
+  path: string
+  newValue: any
+}
+
+export interface RemovedResult {
+  diffType: DiffType.Removed
+  path: string
+  value: any
+}
+
+export interface ModifiedResult {
+  diffType: DiffType.Modified
+  path: string
+  oldValue: any
+  newValue: any
+}
+
+export interface TypeChangedResult {
+  diffType: DiffType.TypeChanged
+  path: string
+  oldValue: any
+  newValue: any
+}
+
+export interface TensorShapeChangedResult {
+  diffType: DiffType.TensorShapeChanged
+  path: string
+  oldShape: Array<number>
+  newShape: Array<number>
+}
+
+export interface TensorStatsChangedResult {
+  diffType: DiffType.TensorStatsChanged
+  path: string
+  oldStats: JsTensorStats
+  newStats: JsTensorStats
+}
+
+export interface TensorDataChangedResult {
+  diffType: DiffType.TensorDataChanged
+  path: string
+  oldMean: number
+  newMean: number
+}
+
+export interface ModelArchitectureChangedResult {
+  diffType: DiffType.ModelArchitectureChanged
+  path: string
+  oldString: string
+  newString: string
+}
+
+export interface WeightSignificantChangeResult {
+  diffType: DiffType.WeightSignificantChange
+  path: string
+  changeMagnitude: number
+}
+
+export interface ActivationFunctionChangedResult {
+  diffType: DiffType.ActivationFunctionChanged
+  path: string
+  oldString: string
+  newString: string
+}
+
+export interface LearningRateChangedResult {
+  diffType: DiffType.LearningRateChanged
+  path: string
+  oldFloat: number
+  newFloat: number
+}
+
+export interface OptimizerChangedResult {
+  diffType: DiffType.OptimizerChanged
+  path: string
+  oldString: string
+  newString: string
+}
+
+export interface LossChangeResult {
+  diffType: DiffType.LossChange
+  path: string
+  oldFloat: number
+  newFloat: number
+}
+
+export interface AccuracyChangeResult {
+  diffType: DiffType.AccuracyChange
+  path: string
+  oldFloat: number
+  newFloat: number
+}
+
+export interface ModelVersionChangedResult {
+  diffType: DiffType.ModelVersionChanged
+  path: string
+  oldString: string
+  newString: string
+}
+
+export interface TensorNanIntroducedResult {
+  diffType: DiffType.TensorNanIntroduced
+  path: string
+  oldStats: JsTensorStats
+  newStats: JsTensorStats
+}
+
+export interface TensorInfIntroducedResult {
+  diffType: DiffType.TensorInfIntroduced
+  path: string
+  oldStats: JsTensorStats
+  newStats: JsTensorStats
+}
+